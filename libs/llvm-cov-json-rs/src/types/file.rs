@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-use crate::{Branch, Expansion};
+use crate::{Branch, Expansion, MCDCRecord};
 
 use super::Summary;
 
@@ -18,6 +18,9 @@ pub struct FileMetrics<'a> {
     pub segments: Vec<Segment>,
     /// Expansion of this file. May be None, if the export of expansions have been disabled.
     pub expansions: Option<Vec<Expansion<'a>>>,
+    /// MC/DC records of the decisions in this file. None if the export
+    /// was created by an LLVM version without MC/DC support.
+    pub mcdc_records: Option<Vec<MCDCRecord>>,
 }
 
 /// Execution count information starting at a point (row and column) in a file.
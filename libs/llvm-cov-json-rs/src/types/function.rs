@@ -1,6 +1,7 @@
 use serde::Deserialize;
+use std::borrow::Cow;
 
-use crate::{Branch, Region};
+use crate::{Branch, MCDCRecord, Region};
 
 
 /// Metrics relate to one specific function.
@@ -13,9 +14,14 @@ pub struct FunctionMetrics<'a> {
     pub branches: Vec<Branch>,
     /// Number of times this function was called.
     pub count: u64,
-    /// Name of the function.
-    pub name: &'a str,
+    /// Name of the function. Borrowed from the export unless it contains escapes
+    /// or was replaced, e.g., by its demangled form.
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
     /// The regions in this function. File ids of the regions are indices into
     /// the `filenames` array.
     pub regions: Vec<Region>,
+    /// MC/DC records of the decisions in this function. None if the export
+    /// was created by an LLVM version without MC/DC support.
+    pub mcdc_records: Option<Vec<MCDCRecord>>,
 }
\ No newline at end of file
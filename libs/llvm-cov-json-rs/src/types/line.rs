@@ -0,0 +1,97 @@
+use crate::{FileMetrics, Segment};
+
+/// Execution count of a single source line, derived from the segments of a file.
+/// Mirrors `LineCoverageStats` in the [LLVM source code](https://github.com/llvm/llvm-project/blob/bd611264993f64decbce178d460caf1d1cb05f59/llvm/lib/ProfileData/Coverage/CoverageMapping.cpp#L1157),
+/// so the counts match the ones shown by `llvm-cov show`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineCoverage {
+    /// The source code line.
+    pub line: u64,
+    /// Number of times this line was executed. Only meaningful if `mapped` is true.
+    pub execution_count: u64,
+    /// Whether this line is instrumented at all. Lines that are skipped (e.g., by the
+    /// preprocessor) or not covered by any region are not mapped.
+    pub mapped: bool,
+    /// Whether more than one region starts on this line.
+    pub has_multiple_regions: bool,
+}
+
+impl LineCoverage {
+    /// Whether the line is instrumented but was never executed.
+    pub fn is_uncovered(&self) -> bool {
+        self.mapped && self.execution_count == 0
+    }
+
+    fn new(line: u64, line_segments: &[&Segment], wrapped_segment: Option<&Segment>) -> Self {
+        // A segment starts a region if it is a non-gap region entry that carries a count.
+        let is_start_of_region = |s: &Segment| !s.is_gap_region && s.has_count && s.is_region_entry;
+
+        let region_starts = line_segments
+            .iter()
+            .filter(|s| is_start_of_region(s))
+            .count();
+        let start_of_skipped_region = line_segments
+            .first()
+            .is_some_and(|s| !s.has_count && s.is_region_entry);
+
+        let mapped = !start_of_skipped_region
+            && (wrapped_segment.is_some_and(|s| s.has_count) || region_starts > 0);
+
+        let mut execution_count = 0;
+        if mapped {
+            // Pick the max count from the region entries and the wrapped segment.
+            if let Some(wrapped) = wrapped_segment {
+                execution_count = wrapped.count;
+            }
+            for segment in line_segments.iter().filter(|s| is_start_of_region(s)) {
+                execution_count = execution_count.max(segment.count);
+            }
+        }
+
+        LineCoverage {
+            line,
+            execution_count,
+            mapped,
+            has_multiple_regions: region_starts > 1,
+        }
+    }
+}
+
+/// Computes the line coverage for a sorted sequence of segments.
+/// Returns one entry for every line between the first and the last segment,
+/// including lines that are not mapped.
+pub fn line_coverage_from_segments(segments: &[Segment]) -> Vec<LineCoverage> {
+    let mut lines = Vec::new();
+    let Some(first) = segments.first() else {
+        return lines;
+    };
+
+    let mut next = 0;
+    let mut line = first.line;
+    let mut wrapped_segment: Option<&Segment> = None;
+    let mut line_segments: Vec<&Segment> = Vec::new();
+
+    while next < segments.len() {
+        // The last segment of the previous line (with segments) wraps into this line.
+        if let Some(last) = line_segments.last() {
+            wrapped_segment = Some(last);
+        }
+        line_segments.clear();
+        while next < segments.len() && segments[next].line == line {
+            line_segments.push(&segments[next]);
+            next += 1;
+        }
+
+        lines.push(LineCoverage::new(line, &line_segments, wrapped_segment));
+        line += 1;
+    }
+
+    lines
+}
+
+impl FileMetrics<'_> {
+    /// Per-line execution counts of this file, computed from `segments`.
+    pub fn line_coverage(&self) -> Vec<LineCoverage> {
+        line_coverage_from_segments(&self.segments)
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Deserializer, de::Error};
+
+use crate::RegionKind;
+
+/// The MC/DC (modified condition/decision coverage) record of a single decision, i.e., a
+/// boolean expression consisting of one or more conditions combined by `&&` and `||`.
+/// Only exported if the binary was built with `-fcoverage-mcdc` (LLVM 18 and later).
+/// See [CoverageExporterJson.cpp](https://github.com/llvm/llvm-project/blob/llvmorg-18.1.8/llvm/tools/llvm-cov/CoverageExporterJson.cpp#L105)
+/// for details regarding the format.
+#[derive(Debug, PartialEq)]
+pub struct MCDCRecord {
+    /// Source line the decision starts.
+    pub line_start: u64,
+    /// Column of `line_start` the decision starts.
+    pub column_start: u64,
+    /// Source line the decision ends.
+    pub line_end: u64,
+    /// Column of `line_end` the decision ends.
+    pub column_end: u64,
+    /// The file id the decision has been expanded to.
+    pub expanded_file_id: u64,
+    /// The kind of the decision region, i.e., [`RegionKind::MCDCDecision`].
+    pub region_kind: RegionKind,
+    /// For every condition of the decision (in source order), whether a pair of executed
+    /// test vectors shows that the condition independently affects the outcome of the decision.
+    pub conditions: Vec<bool>,
+}
+
+impl MCDCRecord {
+    /// Whether every condition of the decision has been shown to independently affect it.
+    pub fn is_covered(&self) -> bool {
+        self.conditions.iter().all(|covered| *covered)
+    }
+}
+
+#[derive(Deserialize)]
+struct MCDCRecordTuple(u64, u64, u64, u64, u64, u64, Vec<bool>);
+
+impl<'de> Deserialize<'de> for MCDCRecord {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de> {
+        let tuple = MCDCRecordTuple::deserialize(deserializer)?;
+        let region_kind = match RegionKind::try_from(tuple.5) {
+            Ok(kind) => kind,
+            Err(err) => return Err(Error::custom(err)),
+        };
+
+        Ok(MCDCRecord {
+            line_start: tuple.0,
+            column_start: tuple.1,
+            line_end: tuple.2,
+            column_end: tuple.3,
+            expanded_file_id: tuple.4,
+            region_kind,
+            conditions: tuple.6,
+        })
+    }
+}
@@ -7,6 +7,9 @@ pub use region::*;
 mod branch;
 pub use branch::*;
 
+mod mcdc;
+pub use mcdc::*;
+
 mod expansion;
 pub use expansion::*;
 
@@ -19,6 +22,9 @@ pub use file::*;
 mod summary;
 pub use summary::*;
 
+mod line;
+pub use line::*;
+
 
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct CoverageReport<'a> {
@@ -34,7 +40,7 @@ pub struct CoverageReport<'a> {
 
 impl<'a> CoverageReport<'a> {
 
-    pub fn from_str(s: &str) -> Result<CoverageReport, serde_json::Error> {
+    pub fn from_str(s: &str) -> Result<CoverageReport<'_>, serde_json::Error> {
         Ok(serde_json::from_str(s)?)
     }
 
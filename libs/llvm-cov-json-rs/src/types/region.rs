@@ -20,7 +20,13 @@ pub enum RegionKind {
     /// A Branch region represents leaf-level boolean expressions and is
     /// associated with two counters, each representing the number of times the
     /// expression evaluates to true or false.
-    Branch
+    Branch,
+    /// A DecisionRegion represents a top-level boolean expression and is
+    /// associated with a variable length bitmap index and condition number.
+    /// Only emitted if MC/DC instrumentation (`-fcoverage-mcdc`) is enabled.
+    MCDCDecision,
+    /// A Branch region that is part of an MC/DC decision, i.e., one of its conditions.
+    MCDCBranch,
 }
 
 impl TryFrom<u64> for RegionKind {
@@ -33,6 +39,8 @@ impl TryFrom<u64> for RegionKind {
             _ if value == RegionKind::Skipped as u64 => RegionKind::Skipped,
             _ if value == RegionKind::Gap as u64 => RegionKind::Gap,
             _ if value == RegionKind::Branch as u64 => RegionKind::Branch,
+            _ if value == RegionKind::MCDCDecision as u64 => RegionKind::MCDCDecision,
+            _ if value == RegionKind::MCDCBranch as u64 => RegionKind::MCDCBranch,
             _ => return Err(format!("Unknown region kind {}", value)),
         };
 
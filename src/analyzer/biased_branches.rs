@@ -0,0 +1,101 @@
+use crate::analyzer::uncovered::get_file_part;
+use llvm_cov_json::CoverageReport;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+/// A branch that was taken in both directions, but far more often in one of them.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BiasedBranch {
+    pub function_name: String,
+    pub file_path: String,
+    pub start_line: u64,
+    pub start_column: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+    pub true_count: u64,
+    pub false_count: u64,
+    /// Ratio of the more frequent direction to the less frequent one.
+    pub ratio: f64,
+}
+
+/// Collects the branches whose more frequent direction was taken at least `threshold`
+/// times as often as the other one, most biased first.
+/// Branches that were never taken in one direction are left to the uncovered analysis.
+pub fn get_biased_branches(coverage_report: &CoverageReport, threshold: f64) -> Vec<BiasedBranch> {
+    let mut biased_branches: Vec<BiasedBranch> = coverage_report.data[0]
+        .functions
+        .iter()
+        .flat_map(|function| {
+            function
+                .branches
+                .iter()
+                .filter(|branch| branch.execution_count > 0 && branch.false_execution_count > 0)
+                .map(move |branch| {
+                    let high = branch.execution_count.max(branch.false_execution_count);
+                    let low = branch.execution_count.min(branch.false_execution_count);
+                    BiasedBranch {
+                        function_name: function.name.to_string(),
+                        file_path: function.filenames[branch.file_id as usize].to_string(),
+                        start_line: branch.line_start,
+                        start_column: branch.column_start,
+                        end_line: branch.line_end,
+                        end_column: branch.column_end,
+                        true_count: branch.execution_count,
+                        false_count: branch.false_execution_count,
+                        ratio: high as f64 / low as f64,
+                    }
+                })
+        })
+        .filter(|branch| branch.ratio >= threshold)
+        .collect();
+
+    biased_branches.sort_by(|a, b| b.ratio.total_cmp(&a.ratio));
+    biased_branches
+}
+
+/// Pretty print the biased branches.
+pub fn write_biased_branches(
+    biased_branches: &[BiasedBranch],
+    threshold: f64,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    writeln!(writer, "\nBiased Branches")?;
+    writeln!(writer, "===============\n")?;
+    writeln!(writer, "Threshold: {}:1\n", threshold)?;
+
+    if biased_branches.is_empty() {
+        writeln!(writer, "No biased branches found.")?;
+        return Ok(());
+    }
+
+    for (i, branch) in biased_branches.iter().enumerate() {
+        let direction = if branch.true_count > branch.false_count {
+            "mostly true"
+        } else {
+            "mostly false"
+        };
+
+        writeln!(
+            writer,
+            "  {}. {} (true: {}, false: {}, {:.1}:1 {}) in {} ({}:{}:{})",
+            i + 1,
+            get_file_part(
+                &branch.file_path,
+                branch.start_line,
+                branch.start_column,
+                branch.end_line,
+                branch.end_column
+            ),
+            branch.true_count,
+            branch.false_count,
+            branch.ratio,
+            direction,
+            branch.function_name,
+            branch.file_path,
+            branch.start_line,
+            branch.start_column
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use crate::analyzer::uncovered::Coverage;
+use crate::diff::{BranchDirection, BranchKey};
+use llvm_cov_json::CoverageReport;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+/// What one of several merged binaries covered.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BinaryCoverage {
+    pub binary: String,
+    /// The coverage of this binary on its own.
+    pub coverage: Coverage,
+    /// Functions that no other binary executed.
+    pub unique_functions: Vec<String>,
+    /// Branch directions that no other binary took.
+    pub unique_branch_directions: Vec<BranchDirection>,
+}
+
+/// The executed functions and taken branch directions of one report.
+fn covered_parts(
+    coverage_report: &CoverageReport,
+) -> (BTreeSet<String>, BTreeSet<BranchDirection>) {
+    let mut functions = BTreeSet::new();
+    let mut branch_directions = BTreeSet::new();
+
+    for function in coverage_report.data[0]
+        .functions
+        .iter()
+        .filter(|f| f.count > 0)
+    {
+        functions.insert(function.name.to_string());
+        for branch in &function.branches {
+            let key = BranchKey {
+                function_name: function.name.to_string(),
+                file_path: function.filenames[branch.file_id as usize].to_string(),
+                line: branch.line_start,
+                column: branch.column_start,
+            };
+            for (direction, count) in [
+                (true, branch.execution_count),
+                (false, branch.false_execution_count),
+            ] {
+                if count > 0 {
+                    branch_directions.insert(BranchDirection {
+                        branch: key.clone(),
+                        direction,
+                    });
+                }
+            }
+        }
+    }
+
+    (functions, branch_directions)
+}
+
+/// Attributes the coverage of the reports of several binaries, given with the binary they
+/// belong to, to the binaries that contributed it.
+pub fn get_binary_coverage(reports: &[(String, CoverageReport)]) -> Vec<BinaryCoverage> {
+    let parts: Vec<_> = reports
+        .iter()
+        .map(|(_, report)| covered_parts(report))
+        .collect();
+
+    // how many binaries covered each function and branch direction
+    let mut function_counts: BTreeMap<&String, usize> = BTreeMap::new();
+    let mut direction_counts: BTreeMap<&BranchDirection, usize> = BTreeMap::new();
+    for (functions, branch_directions) in &parts {
+        for function in functions {
+            *function_counts.entry(function).or_default() += 1;
+        }
+        for direction in branch_directions {
+            *direction_counts.entry(direction).or_default() += 1;
+        }
+    }
+
+    reports
+        .iter()
+        .zip(&parts)
+        .map(
+            |((binary, report), (functions, branch_directions))| BinaryCoverage {
+                binary: binary.clone(),
+                coverage: Coverage::from_report(report),
+                unique_functions: functions
+                    .iter()
+                    .filter(|function| function_counts[function] == 1)
+                    .cloned()
+                    .collect(),
+                unique_branch_directions: branch_directions
+                    .iter()
+                    .filter(|direction| direction_counts[direction] == 1)
+                    .cloned()
+                    .collect(),
+            },
+        )
+        .collect()
+}
+
+/// Pretty print what each binary covered.
+pub fn write_binary_coverage(
+    binaries: &[BinaryCoverage],
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    writeln!(writer, "\nCoverage by Binary")?;
+    writeln!(writer, "==================\n")?;
+
+    for binary in binaries {
+        writeln!(writer, "{}", binary.binary)?;
+        writeln!(
+            writer,
+            "  Branches: {}/{}, functions: {}/{}",
+            binary.coverage.covered_branches,
+            binary.coverage.total_branches,
+            binary.coverage.covered_functions,
+            binary.coverage.total_functions
+        )?;
+
+        if !binary.unique_functions.is_empty() {
+            writeln!(writer, "  Only executed by this binary:")?;
+            for function in &binary.unique_functions {
+                writeln!(writer, "    {}", function)?;
+            }
+        }
+        if !binary.unique_branch_directions.is_empty() {
+            writeln!(writer, "  Branch directions only taken by this binary:")?;
+            for direction in &binary.unique_branch_directions {
+                writeln!(
+                    writer,
+                    "    {} ({}:{}:{}): {}",
+                    direction.branch.function_name,
+                    direction.branch.file_path,
+                    direction.branch.line,
+                    direction.branch.column,
+                    direction.direction
+                )?;
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
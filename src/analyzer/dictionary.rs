@@ -0,0 +1,330 @@
+use super::Analyzer;
+use crate::analyzer::uncovered::{
+    get_file_lines, get_partially_covered_predicates, PartiallyCoveredPredicate,
+};
+use crate::error::GetCovError;
+use llvm_cov_json::CoverageReport;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Macros are resolved up to this depth, e.g. `#define A B` / `#define B "x"` needs two steps.
+const MAX_MACRO_DEPTH: usize = 8;
+
+static DEFINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*#\s*define\s+([A-Za-z_][A-Za-z0-9_]*)(?:\s+(.*))?$").unwrap()
+});
+static INCLUDE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\s*#\s*include\s*"([^"]+)""#).unwrap());
+/// String and character literals, numbers, and identifiers.
+static TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#""((?:[^"\\]|\\.)*)"|'((?:[^'\\]|\\.)+)'|\b(0[xX][0-9a-fA-F]+|[0-9]+)[uUlL]*\b|\b([A-Za-z_][A-Za-z0-9_]*)\b"#,
+    )
+    .unwrap()
+});
+
+/// A fuzzer dictionary entry and the predicate it was extracted from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DictionaryEntry {
+    pub name: String,
+    /// The value, escaped for the dictionary format.
+    pub value: String,
+    pub function_name: String,
+    pub file_path: String,
+    pub line: u64,
+    /// The source text the value was found in.
+    pub source: String,
+}
+
+/// Extracts the operands of predicates that always evaluate to the same value, i.e., string,
+/// character and integer literals as well as `#define`d constants, and turns them into an
+/// AFL/libFuzzer dictionary.
+pub struct DictionaryAnalyzer {
+    results: Option<Vec<DictionaryEntry>>,
+}
+
+impl DictionaryAnalyzer {
+    pub fn new() -> Self {
+        Self { results: None }
+    }
+
+    /// Writes the entries in the AFL/libFuzzer dictionary format, i.e., `name="value"` lines.
+    pub fn write_dictionary(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let entries = self.results.as_ref().unwrap();
+        writeln!(writer, "# Generated by getcov: {} entries", entries.len())?;
+
+        let mut last_source = None;
+        for entry in entries {
+            if last_source != Some((&entry.file_path, entry.line)) {
+                writeln!(
+                    writer,
+                    "\n# {}:{} in {}: {}",
+                    entry.file_path,
+                    entry.line,
+                    entry.function_name,
+                    entry.source.replace('\n', " ")
+                )?;
+                last_source = Some((&entry.file_path, entry.line));
+            }
+            writeln!(writer, "{}=\"{}\"", entry.name, entry.value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes a value for the dictionary format: printable ASCII is kept, everything else is hex-escaped.
+fn escape_value(value: &[u8]) -> String {
+    value
+        .iter()
+        .map(|&byte| match byte {
+            b'"' | b'\\' => format!("\\{}", byte as char),
+            0x20..=0x7e => (byte as char).to_string(),
+            _ => format!("\\x{:02x}", byte),
+        })
+        .collect()
+}
+
+/// Parses the body of a C string or character literal (without quotes) into bytes.
+fn unescape_c_literal(literal: &str) -> Vec<u8> {
+    let bytes = literal.as_bytes();
+    let mut value = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'\\' || i + 1 == bytes.len() {
+            value.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        i += 1;
+        match bytes[i] {
+            b'n' => value.push(b'\n'),
+            b't' => value.push(b'\t'),
+            b'r' => value.push(b'\r'),
+            b'a' => value.push(0x07),
+            b'b' => value.push(0x08),
+            b'f' => value.push(0x0c),
+            b'v' => value.push(0x0b),
+            b'x' => {
+                let digits: String = literal[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_hexdigit())
+                    .take(2)
+                    .collect();
+                value.push(u8::from_str_radix(&digits, 16).unwrap_or(0));
+                i += digits.len();
+            }
+            b'0'..=b'7' => {
+                let digits: String = literal[i..]
+                    .chars()
+                    .take_while(|c| ('0'..='7').contains(c))
+                    .take(3)
+                    .collect();
+                value.push(u8::from_str_radix(&digits, 8).unwrap_or(0));
+                i += digits.len() - 1;
+            }
+            other => value.push(other),
+        }
+        i += 1;
+    }
+
+    value
+}
+
+/// Parses a C integer literal, ignoring suffixes like `U` or `UL`.
+fn parse_integer(literal: &str) -> Option<u64> {
+    let literal = literal.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()
+    } else if literal.len() > 1 && literal.starts_with('0') {
+        u64::from_str_radix(&literal[1..], 8).ok()
+    } else {
+        literal.parse().ok()
+    }
+}
+
+/// Turns an integer into dictionary values: its little and big endian representation
+/// in the smallest fitting width, and its decimal text for text-based formats.
+/// 0 and 1 are skipped, as they appear everywhere and only add noise.
+fn integer_values(value: u64) -> Vec<Vec<u8>> {
+    if value <= 1 {
+        return Vec::new();
+    }
+
+    let width = match value {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x1_0000..=0xffff_ffff => 4,
+        _ => 8,
+    };
+    let little_endian = value.to_le_bytes()[..width].to_vec();
+    let mut big_endian = little_endian.clone();
+    big_endian.reverse();
+
+    let mut values = vec![little_endian];
+    if width > 1 {
+        values.push(big_endian);
+    }
+    values.push(value.to_string().into_bytes());
+    values
+}
+
+/// Object-like macros (`#define NAME value`) of a file and the local headers it includes.
+fn collect_macros(
+    file_path: &str,
+    macros: &mut HashMap<String, String>,
+    visited: &mut HashSet<String>,
+) {
+    if !visited.insert(file_path.to_string()) || !Path::new(file_path).is_file() {
+        return;
+    }
+
+    let directory = Path::new(file_path).parent().unwrap_or(Path::new(""));
+
+    for line in get_file_lines(file_path) {
+        if let Some(captures) = DEFINE_RE.captures(&line) {
+            // function-like macros are not resolved
+            if let Some(value) = captures.get(2) {
+                let value = value.as_str().split("//").next().unwrap_or("").trim();
+                macros
+                    .entry(captures[1].to_string())
+                    .or_insert_with(|| value.to_string());
+            }
+        } else if let Some(captures) = INCLUDE_RE.captures(&line) {
+            let header = directory.join(&captures[1]);
+            collect_macros(&header.to_string_lossy(), macros, visited);
+        }
+    }
+}
+
+/// Extracts the literal operands of an expression, resolving object-like macros.
+fn extract_values(
+    expression: &str,
+    macros: &HashMap<String, String>,
+    depth: usize,
+) -> Vec<Vec<u8>> {
+    let mut values = Vec::new();
+
+    for captures in TOKEN_RE.captures_iter(expression) {
+        if let Some(string) = captures.get(1) {
+            let value = unescape_c_literal(string.as_str());
+            if !value.is_empty() {
+                values.push(value);
+            }
+        } else if let Some(character) = captures.get(2) {
+            values.push(unescape_c_literal(character.as_str()));
+        } else if let Some(number) = captures.get(3) {
+            if let Some(value) = parse_integer(number.as_str()) {
+                values.extend(integer_values(value));
+            }
+        } else if let Some(identifier) = captures.get(4) {
+            if depth < MAX_MACRO_DEPTH {
+                if let Some(definition) = macros.get(identifier.as_str()) {
+                    values.extend(extract_values(definition, macros, depth + 1));
+                }
+            }
+        }
+    }
+
+    values
+}
+
+/// The text of a predicate and its surrounding expression: the whole source lines it is on.
+/// Inside a macro, only the predicate itself is taken from the macro body, and the line where
+/// the macro is used is added, since it contains the arguments.
+fn get_predicate_context(predicate: &PartiallyCoveredPredicate) -> (String, u64) {
+    let lines = get_file_lines(&predicate.file_path);
+    let start = (predicate.start_line as usize)
+        .saturating_sub(1)
+        .min(lines.len());
+    let end = (predicate.end_line as usize).clamp(start, lines.len());
+
+    let Some(use_site) = predicate.expansions.last() else {
+        return (lines[start..end].join("\n"), predicate.start_line);
+    };
+
+    let mut context = if predicate.start_line == predicate.end_line {
+        lines
+            .get(start)
+            .and_then(|line| {
+                line.get(
+                    (predicate.start_column as usize).saturating_sub(1)
+                        ..(predicate.end_column as usize).saturating_sub(1),
+                )
+            })
+            .unwrap_or("")
+            .to_string()
+    } else {
+        lines[start..end].join("\n")
+    };
+    let use_lines = get_file_lines(&use_site.file_path);
+    if let Some(use_line) = use_lines.get((use_site.line as usize).saturating_sub(1)) {
+        context.push('\n');
+        context.push_str(use_line);
+    }
+
+    (context, use_site.line)
+}
+
+impl Analyzer for DictionaryAnalyzer {
+    fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
+        let mut entries = Vec::new();
+        let mut seen_values = HashSet::new();
+        let mut macros_by_file: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+        for function in &coverage_report.data[0].functions {
+            let Some(predicates) = get_partially_covered_predicates(function) else {
+                continue;
+            };
+
+            for predicate in &predicates {
+                let macros = macros_by_file
+                    .entry(predicate.file_path.clone())
+                    .or_insert_with(|| {
+                        let mut macros = HashMap::new();
+                        collect_macros(&predicate.file_path, &mut macros, &mut HashSet::new());
+                        macros
+                    });
+                let (context, line) = get_predicate_context(predicate);
+
+                for (index, value) in extract_values(&context, macros, 0).into_iter().enumerate() {
+                    if !seen_values.insert(value.clone()) {
+                        continue;
+                    }
+                    entries.push(DictionaryEntry {
+                        name: format!("{}_{}_{}", function.name, line, index)
+                            .replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_"),
+                        value: escape_value(&value),
+                        function_name: function.name.to_string(),
+                        file_path: predicate.file_path.clone(),
+                        line,
+                        source: context.trim().to_string(),
+                    });
+                }
+            }
+        }
+
+        self.results = Some(entries);
+        Ok(())
+    }
+
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
+        Ok(())
+    }
+
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        // the dictionary itself is the text output, so it can be piped into a file
+        self.write_dictionary(writer)?;
+        Ok(())
+    }
+}
@@ -1,9 +1,14 @@
 use super::Analyzer;
 use crate::error::GetCovError;
+use crate::merge::{source_location, Instantiation, SourceLocation};
+use crate::schema::{RunMetadata, SCHEMA_VERSION};
 use llvm_cov_json::CoverageReport;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FunctionInfo {
     pub name: String,
     pub file_path: String,
@@ -11,54 +16,94 @@ pub struct FunctionInfo {
     pub start_column: u64,
     pub end_line: u64,
     pub end_column: u64,
+    /// The instantiations merged into this function, if it is a template or generic function.
+    #[serde(default)]
+    pub instantiations: Vec<Instantiation>,
+}
+
+/// The JSON output of `--all`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FunctionList {
+    /// The version of the output format, increased on incompatible changes.
+    pub schema_version: u32,
+    pub metadata: RunMetadata,
+    pub functions: Vec<FunctionInfo>,
 }
 
 pub struct ExtractFunctionsAnalyzer {
-    results: Option<Vec<FunctionInfo>>
+    instantiations: HashMap<SourceLocation, Vec<Instantiation>>,
+    metadata: RunMetadata,
+    results: Option<FunctionList>,
 }
 
 impl ExtractFunctionsAnalyzer {
-    pub fn new() -> Self {
-        Self { results: None }
+    /// `instantiations` are the instantiations of the merged template and generic functions,
+    /// and `metadata` describes the run for the JSON output.
+    pub fn new(
+        instantiations: HashMap<SourceLocation, Vec<Instantiation>>,
+        metadata: RunMetadata,
+    ) -> Self {
+        Self {
+            instantiations,
+            metadata,
+            results: None,
+        }
     }
 }
 
 impl Analyzer for ExtractFunctionsAnalyzer {
     fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
-        self.results = Some(coverage_report.data[0]
-            .functions
-            .iter()
-            .map(|f| FunctionInfo {
-                name: f.name.to_string(),
-                file_path: f.filenames[f.regions[0].file_id as usize].to_string(),
-                start_line: f.regions[0].line_start,
-                start_column: f.regions[0].column_start,
-                end_line: f.regions[0].line_end,
-                end_column: f.regions[0].column_end,
-            })
-            .collect());
+        self.results = Some(FunctionList {
+            schema_version: SCHEMA_VERSION,
+            metadata: self.metadata.clone(),
+            functions: coverage_report.data[0]
+                .functions
+                .iter()
+                .map(|f| FunctionInfo {
+                    name: f.name.to_string(),
+                    file_path: f.filenames[f.regions[0].file_id as usize].to_string(),
+                    start_line: f.regions[0].line_start,
+                    start_column: f.regions[0].column_start,
+                    end_line: f.regions[0].line_end,
+                    end_column: f.regions[0].column_end,
+                    instantiations: source_location(f)
+                        .and_then(|location| self.instantiations.get(&location))
+                        .cloned()
+                        .unwrap_or_default(),
+                })
+                .collect(),
+        });
         Ok(())
     }
 
-    fn output_json(&self) -> Result<(), GetCovError> {
-        serde_json::to_writer_pretty(std::io::stdout(), self.results.as_ref().unwrap())?;
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
         Ok(())
     }
 
-    fn output_text(&self) {
-        let data = self.results.as_ref().unwrap();
-        println!("Functions found in the program:");
-        println!("==============================\n");
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        let data = &self.results.as_ref().unwrap().functions;
+        writeln!(writer, "Functions found in the program:")?;
+        writeln!(writer, "==============================\n")?;
 
         for (i, function) in data.iter().enumerate() {
-            println!(
+            writeln!(
+                writer,
                 "{}. {} ({}:{}:{})",
                 i + 1,
                 function.name,
                 function.file_path,
                 function.start_line,
                 function.start_column
-            );
+            )?;
+            for instantiation in &function.instantiations {
+                writeln!(
+                    writer,
+                    "     {} (executed {} times)",
+                    instantiation.name, instantiation.count
+                )?;
+            }
         }
+        Ok(())
     }
 }
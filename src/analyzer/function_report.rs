@@ -0,0 +1,400 @@
+use super::Analyzer;
+use crate::analyzer::line_coverage::LineCoverageMap;
+use crate::analyzer::uncovered::{
+    get_file_lines, get_file_part, get_uncovered_regions, CodeRegion, RegionCategoryFilter,
+};
+use crate::demangle::source_function_name;
+use crate::error::GetCovError;
+use llvm_cov_json::{Branch, CoverageReport, FunctionMetrics, RegionKind};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::sync::LazyLock;
+
+/// A source line of the function, with its execution count if it is instrumented.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotatedLine {
+    pub line: u64,
+    pub count: Option<u64>,
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BranchCount {
+    pub file_path: String,
+    pub start_line: u64,
+    pub start_column: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+    pub condition: String,
+    pub true_count: u64,
+    pub false_count: u64,
+}
+
+/// An uncovered region and the branch right in front of it, which usually decides if it runs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GuardedRegion {
+    pub region: CodeRegion,
+    pub guard: Option<BranchCount>,
+}
+
+/// Another function of the report that calls or is called by the reported one.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelatedFunction {
+    pub name: String,
+    pub execution_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FunctionReport {
+    pub function_name: String,
+    pub file_path: String,
+    pub start_line: u64,
+    pub end_line: u64,
+    pub execution_count: u64,
+    pub lines: Vec<AnnotatedLine>,
+    pub branches: Vec<BranchCount>,
+    pub uncovered_regions: Vec<GuardedRegion>,
+    pub callers: Vec<RelatedFunction>,
+    pub callees: Vec<RelatedFunction>,
+}
+
+/// Reports everything known about the functions matching a name or regex: annotated source,
+/// all branches, the uncovered regions with their guards, and callers and callees.
+/// Calls are found in the source text, so only functions present in the report are listed.
+pub struct FunctionReportAnalyzer {
+    pattern: String,
+    name_re: Regex,
+    results: Option<Vec<FunctionReport>>,
+}
+
+impl FunctionReportAnalyzer {
+    /// `pattern` is either a function name or a regex that has to match the whole name.
+    pub fn new(pattern: &str) -> Result<Self, GetCovError> {
+        let name_re = Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+            GetCovError::ArgParse(format!("Invalid function pattern '{}': {}", pattern, e))
+        })?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            name_re,
+            results: None,
+        })
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        name == self.pattern || self.name_re.is_match(name)
+    }
+}
+
+fn get_branch_count(function: &FunctionMetrics, branch: &Branch) -> BranchCount {
+    let file_path = function.filenames[branch.file_id as usize];
+    BranchCount {
+        file_path: file_path.to_string(),
+        start_line: branch.line_start,
+        start_column: branch.column_start,
+        end_line: branch.line_end,
+        end_column: branch.column_end,
+        condition: get_file_part(
+            file_path,
+            branch.line_start,
+            branch.column_start,
+            branch.line_end,
+            branch.column_end,
+        ),
+        true_count: branch.execution_count,
+        false_count: branch.false_execution_count,
+    }
+}
+
+/// The branch that ends closest in front of the region, on the same or the previous line.
+fn get_guard<'a>(function: &'a FunctionMetrics, region: &CodeRegion) -> Option<&'a Branch> {
+    function
+        .branches
+        .iter()
+        .filter(|branch| {
+            function.filenames[branch.file_id as usize] == region.file_path
+                && (branch.line_end, branch.column_end) <= (region.start_line, region.start_column)
+                && branch.line_end + 1 >= region.start_line
+        })
+        .max_by_key(|branch| (branch.line_end, branch.column_end))
+}
+
+/// The span of the function in its own file, taken from its first code region.
+fn get_function_span<'a>(function: &FunctionMetrics<'a>) -> Option<(&'a str, u64, u64)> {
+    let region = function
+        .regions
+        .iter()
+        .find(|region| region.kind == RegionKind::Code)?;
+    Some((
+        function.filenames[region.file_id as usize],
+        region.line_start,
+        region.line_end,
+    ))
+}
+
+static CALL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b([A-Za-z_]\w*)\s*\(").unwrap());
+
+/// The identifier a function is called by in the source: the name without the `file:` prefix
+/// of internal-linkage functions, qualifiers, parameters and template arguments.
+/// E.g., `main.c:helper` becomes `helper`, `ns::Box<int>::get(int) const` becomes `get`.
+fn call_name(name: &str) -> String {
+    let name = source_function_name(name);
+    match name.rsplit_once(':') {
+        Some((_, base)) => base.to_string(),
+        None => name,
+    }
+}
+
+/// The calls between the functions of the report, found by scanning the source of every
+/// function once. Functions are matched by their `call_name`.
+struct CallIndex {
+    /// The names called by each function, by function index.
+    callees: Vec<HashSet<String>>,
+    /// The indices of the functions calling each name.
+    callers: HashMap<String, Vec<usize>>,
+    /// The call name of each function, by function index.
+    names: Vec<String>,
+}
+
+impl CallIndex {
+    fn new(functions: &[FunctionMetrics]) -> Self {
+        let mut sources: HashMap<&str, Vec<String>> = HashMap::new();
+        let mut callees = Vec::with_capacity(functions.len());
+        let mut callers: HashMap<String, Vec<usize>> = HashMap::new();
+        let names: Vec<String> = functions
+            .iter()
+            .map(|function| call_name(&function.name))
+            .collect();
+
+        for (index, function) in functions.iter().enumerate() {
+            let mut called = HashSet::new();
+            if let Some((file_path, start_line, end_line)) = get_function_span(function) {
+                let lines = sources
+                    .entry(file_path)
+                    .or_insert_with(|| get_file_lines(file_path));
+                let first = (start_line as usize).saturating_sub(1).min(lines.len());
+                let last = (end_line as usize).clamp(first, lines.len());
+
+                for (offset, line) in lines[first..last].iter().enumerate() {
+                    for captures in CALL_RE.captures_iter(line) {
+                        let name = &captures[1];
+                        // the first line declares the function, its own name there is no call
+                        if offset == 0 && name == names[index] {
+                            continue;
+                        }
+                        called.insert(name.to_string());
+                    }
+                }
+            }
+
+            for name in &called {
+                callers.entry(name.clone()).or_default().push(index);
+            }
+            callees.push(called);
+        }
+
+        Self {
+            callees,
+            callers,
+            names,
+        }
+    }
+
+    /// The indices of the functions whose source calls the function at `index`.
+    fn callers_of(&self, index: usize) -> &[usize] {
+        self.callers
+            .get(&self.names[index])
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Checks if the source of the function at `index` calls the function at `callee`.
+    fn calls(&self, index: usize, callee: usize) -> bool {
+        self.callees[index].contains(&self.names[callee])
+    }
+}
+
+fn get_function_report(
+    coverage_report: &CoverageReport,
+    line_coverage: &LineCoverageMap,
+    call_index: &CallIndex,
+    index: usize,
+) -> Option<FunctionReport> {
+    let functions = &coverage_report.data[0].functions;
+    let function = &functions[index];
+    let (file_path, start_line, end_line) = get_function_span(function)?;
+
+    let counts = line_coverage.line_counts(file_path, start_line, end_line);
+    let lines = get_file_lines(file_path)
+        .into_iter()
+        .enumerate()
+        .map(|(index, source)| (index as u64 + 1, source))
+        .filter(|(line, _)| (start_line..=end_line).contains(line))
+        .map(|(line, source)| AnnotatedLine {
+            line,
+            count: counts.iter().find(|c| c.line == line).map(|c| c.count),
+            source,
+        })
+        .collect();
+
+    let uncovered_regions = get_uncovered_regions(function, &RegionCategoryFilter::default())
+        .into_iter()
+        .map(|region| GuardedRegion {
+            guard: get_guard(function, &region).map(|branch| get_branch_count(function, branch)),
+            region,
+        })
+        .collect();
+
+    let related = |other: &FunctionMetrics| RelatedFunction {
+        name: other.name.to_string(),
+        execution_count: other.count,
+    };
+
+    Some(FunctionReport {
+        function_name: function.name.to_string(),
+        file_path: file_path.to_string(),
+        start_line,
+        end_line,
+        execution_count: function.count,
+        lines,
+        branches: function
+            .branches
+            .iter()
+            .map(|branch| get_branch_count(function, branch))
+            .collect(),
+        uncovered_regions,
+        callers: call_index
+            .callers_of(index)
+            .iter()
+            .map(|&caller| related(&functions[caller]))
+            .collect(),
+        callees: (0..functions.len())
+            .filter(|&callee| call_index.calls(index, callee))
+            .map(|callee| related(&functions[callee]))
+            .collect(),
+    })
+}
+
+impl Analyzer for FunctionReportAnalyzer {
+    fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
+        let functions = &coverage_report.data[0].functions;
+        let line_coverage = LineCoverageMap::new(coverage_report);
+        let call_index = CallIndex::new(functions);
+        let reports: Vec<FunctionReport> = (0..functions.len())
+            .filter(|&index| self.matches(&functions[index].name))
+            .filter_map(|index| {
+                get_function_report(coverage_report, &line_coverage, &call_index, index)
+            })
+            .collect();
+
+        if reports.is_empty() {
+            return Err(GetCovError::Coverage(format!(
+                "No function matches '{}'",
+                self.pattern
+            )));
+        }
+
+        self.results = Some(reports);
+        Ok(())
+    }
+
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
+        Ok(())
+    }
+
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        for report in self.results.as_ref().unwrap() {
+            write_function_report(report, writer)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_function_report(report: &FunctionReport, writer: &mut dyn Write) -> io::Result<()> {
+    writeln!(writer, "Function: {}", report.function_name)?;
+    writeln!(
+        writer,
+        "Location: {}:{}-{}",
+        report.file_path, report.start_line, report.end_line
+    )?;
+    writeln!(writer, "Executed: {} times\n", report.execution_count)?;
+
+    for line in &report.lines {
+        let count = line.count.map(|c| c.to_string()).unwrap_or_default();
+        writeln!(writer, "{:>10} | {:>5} | {}", count, line.line, line.source)?;
+    }
+
+    if !report.branches.is_empty() {
+        writeln!(writer, "\nBranches:")?;
+        writeln!(writer, "---------")?;
+        for (i, branch) in report.branches.iter().enumerate() {
+            writeln!(
+                writer,
+                "  {}. {} ({}:{}:{}): true {}, false {}",
+                i + 1,
+                branch.condition,
+                branch.file_path,
+                branch.start_line,
+                branch.start_column,
+                branch.true_count,
+                branch.false_count
+            )?;
+        }
+    }
+
+    if !report.uncovered_regions.is_empty() {
+        writeln!(writer, "\nUncovered Regions:")?;
+        writeln!(writer, "-----------------")?;
+        for (i, guarded) in report.uncovered_regions.iter().enumerate() {
+            let region = &guarded.region;
+            writeln!(
+                writer,
+                "  {}. {}:{}:{} - {}:{} ({})",
+                i + 1,
+                region.file_path,
+                region.start_line,
+                region.start_column,
+                region.end_line,
+                region.end_column,
+                region
+                    .category
+                    .map_or("unknown", |category| category.name())
+            )?;
+            match &guarded.guard {
+                Some(guard) => writeln!(
+                    writer,
+                    "     guarded by: {} (true {}, false {})",
+                    guard.condition, guard.true_count, guard.false_count
+                )?,
+                None => writeln!(writer, "     guarded by: (unknown)")?,
+            }
+        }
+    }
+
+    write_related("Callers", &report.callers, writer)?;
+    write_related("Callees", &report.callees, writer)?;
+    writeln!(writer, "\n---------------------------------\n")?;
+    Ok(())
+}
+
+fn write_related(
+    title: &str,
+    functions: &[RelatedFunction],
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    if functions.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "\n{}:", title)?;
+    for function in functions {
+        writeln!(
+            writer,
+            "  {} (executed {} times)",
+            function.name, function.execution_count
+        )?;
+    }
+    Ok(())
+}
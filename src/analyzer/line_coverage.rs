@@ -0,0 +1,192 @@
+use super::Analyzer;
+use crate::error::GetCovError;
+use llvm_cov_json::{CoverageReport, LineCoverage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Per-line execution counts of every file in a coverage report, keyed by file path.
+pub struct LineCoverageMap {
+    files: HashMap<String, Vec<LineCoverage>>,
+}
+
+impl LineCoverageMap {
+    pub fn new(coverage_report: &CoverageReport) -> Self {
+        let files = coverage_report.data[0]
+            .files
+            .iter()
+            .map(|file| (file.filename.to_string(), file.line_coverage()))
+            .collect();
+        Self { files }
+    }
+
+    /// Returns the instrumented but never executed lines of a file between
+    /// `start_line` and `end_line` (both inclusive).
+    pub fn uncovered_lines(&self, file_path: &str, start_line: u64, end_line: u64) -> Vec<u64> {
+        self.files
+            .get(file_path)
+            .map(|lines| {
+                lines
+                    .iter()
+                    .filter(|l| l.line >= start_line && l.line <= end_line && l.is_uncovered())
+                    .map(|l| l.line)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the execution counts of the instrumented lines of a file between
+    /// `start_line` and `end_line` (both inclusive).
+    pub fn line_counts(&self, file_path: &str, start_line: u64, end_line: u64) -> Vec<LineCount> {
+        self.files
+            .get(file_path)
+            .map(|lines| {
+                lines
+                    .iter()
+                    .filter(|l| l.line >= start_line && l.line <= end_line && l.mapped)
+                    .map(|l| LineCount {
+                        line: l.line,
+                        count: l.execution_count,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the instrumented but never executed lines of every file.
+    /// Files without any uncovered line are omitted.
+    pub fn uncovered_lines_by_file(&self) -> Vec<(String, Vec<u64>)> {
+        let mut result: Vec<(String, Vec<u64>)> = self
+            .files
+            .iter()
+            .map(|(file_path, lines)| {
+                let uncovered = lines
+                    .iter()
+                    .filter(|l| l.is_uncovered())
+                    .map(|l| l.line)
+                    .collect();
+                (file_path.clone(), uncovered)
+            })
+            .filter(|(_, lines): &(String, Vec<u64>)| !lines.is_empty())
+            .collect();
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LineCount {
+    pub line: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileLineCoverage {
+    pub file_path: String,
+    pub covered_lines: u64,
+    pub total_lines: u64,
+    pub uncovered_lines: Vec<u64>,
+    /// Execution counts of all instrumented lines.
+    pub lines: Vec<LineCount>,
+}
+
+/// Reports the execution count of every instrumented line, file by file.
+pub struct LineCoverageAnalyzer {
+    results: Option<Vec<FileLineCoverage>>,
+}
+
+impl LineCoverageAnalyzer {
+    pub fn new() -> Self {
+        Self { results: None }
+    }
+}
+
+impl Analyzer for LineCoverageAnalyzer {
+    fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
+        self.results = Some(
+            coverage_report.data[0]
+                .files
+                .iter()
+                .map(|file| {
+                    let lines: Vec<LineCount> = file
+                        .line_coverage()
+                        .into_iter()
+                        .filter(|l| l.mapped)
+                        .map(|l| LineCount {
+                            line: l.line,
+                            count: l.execution_count,
+                        })
+                        .collect();
+
+                    FileLineCoverage {
+                        file_path: file.filename.to_string(),
+                        covered_lines: lines.iter().filter(|l| l.count > 0).count() as u64,
+                        total_lines: lines.len() as u64,
+                        uncovered_lines: lines
+                            .iter()
+                            .filter(|l| l.count == 0)
+                            .map(|l| l.line)
+                            .collect(),
+                        lines,
+                    }
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
+        Ok(())
+    }
+
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        let data = self.results.as_ref().unwrap();
+        writeln!(writer, "Line Coverage Report")?;
+        writeln!(writer, "====================\n")?;
+
+        for file in data {
+            let percent = if file.total_lines == 0 {
+                100.0
+            } else {
+                file.covered_lines as f64 * 100.0 / file.total_lines as f64
+            };
+            writeln!(
+                writer,
+                "{}: {}/{} lines ({:.2}%)",
+                file.file_path, file.covered_lines, file.total_lines, percent
+            )?;
+            if !file.uncovered_lines.is_empty() {
+                writeln!(
+                    writer,
+                    "  Uncovered: {}",
+                    format_line_ranges(&file.uncovered_lines)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a sorted list of line numbers as compact ranges, e.g. `3-5, 9, 12-13`.
+pub fn format_line_ranges(lines: &[u64]) -> String {
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,264 @@
+use super::Analyzer;
+use crate::analyzer::uncovered::{get_file_line, get_file_part};
+use crate::error::GetCovError;
+use llvm_cov_json::{Branch, CoverageReport, FunctionMetrics, RegionKind};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::sync::LazyLock;
+
+static WHILE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^A-Za-z0-9_])(while)\s*\(\s*$").unwrap());
+static FOR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^A-Za-z0-9_])(for)\s*\([^;]*;\s*$").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LoopKind {
+    For,
+    While,
+    DoWhile,
+}
+
+/// A loop and how often its body ran compared to how often the loop was entered.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoopInfo {
+    pub function_name: String,
+    pub file_path: String,
+    pub kind: LoopKind,
+    /// Position of the loop keyword (`for` or `while`).
+    pub line: u64,
+    pub column: u64,
+    pub condition: String,
+    /// Number of times the loop was reached.
+    pub entries: u64,
+    /// Number of times the loop body ran.
+    pub iterations: u64,
+    /// Number of times the loop condition evaluated to false.
+    pub exits: u64,
+    /// The loop was entered and the body never ran more often than that, so there was
+    /// no back-edge beyond the entry count and no entry is known to have iterated twice.
+    pub never_iterated_more_than_once: bool,
+    /// The loop was entered and the condition never ended it, so no entry can have
+    /// skipped the body. Always false for `do`/`while` loops.
+    pub never_skipped: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Output {
+    total_loops: u64,
+    flagged_loops: Vec<LoopInfo>,
+}
+
+/// Finds loops whose body never ran more than once per entry, or that were never skipped.
+/// Loops are identified by loop-condition branches preceded by `for`/`while` in the source.
+/// Only aggregated counts are available, so a false exit of the condition can't be told
+/// apart from a zero-trip one. A loop is only reported as never skipped if the condition
+/// never ended it, e.g., when it was always left by `break` or `return`. The other flag is
+/// a heuristic: a loop entered twice that was skipped once and iterated twice once looks
+/// like a single-iteration loop.
+pub struct LoopAnalyzer {
+    results: Option<Output>,
+}
+
+impl LoopAnalyzer {
+    pub fn new() -> Self {
+        Self { results: None }
+    }
+}
+
+/// The number of times the code around `line`:`column` was executed, i.e., the count
+/// of the innermost code region containing that position.
+pub fn get_enclosing_count(
+    function: &FunctionMetrics,
+    file_id: u64,
+    line: u64,
+    column: u64,
+) -> u64 {
+    function
+        .regions
+        .iter()
+        .filter(|region| {
+            region.kind == RegionKind::Code
+                && region.file_id == file_id
+                && (region.line_start, region.column_start) <= (line, column)
+                && (region.line_end, region.column_end) > (line, column)
+        })
+        .max_by_key(|region| (region.line_start, region.column_start))
+        .map(|region| region.execution_count)
+        .unwrap_or(0)
+}
+
+/// Checks if a branch is the whole condition of a loop, and returns the loop kind
+/// and the column of the loop keyword if so.
+fn detect_loop(file_path: &str, branch: &Branch) -> Option<(LoopKind, u64)> {
+    // the text in front of the condition on the same line, and the rest of the line after it
+    let first_line = get_file_line(file_path, branch.line_start);
+    let last_line = get_file_line(file_path, branch.line_end);
+    let prefix = first_line
+        .get(..(branch.column_start as usize).saturating_sub(1))
+        .unwrap_or("");
+    let suffix = last_line
+        .get((branch.column_end as usize).saturating_sub(1)..)
+        .unwrap_or("");
+
+    if let Some(captures) = FOR_RE.captures(prefix) {
+        let keyword = captures.get(1).unwrap();
+        return suffix
+            .trim_start()
+            .starts_with(';')
+            .then_some((LoopKind::For, keyword.start() as u64 + 1));
+    }
+
+    let keyword = WHILE_RE.captures(prefix)?.get(1).unwrap();
+    let after_condition = suffix.trim_start().strip_prefix(')')?;
+    let is_do_while = prefix[..keyword.start()].trim_end().ends_with('}')
+        || after_condition.trim_start().starts_with(';');
+    let kind = if is_do_while {
+        LoopKind::DoWhile
+    } else {
+        LoopKind::While
+    };
+
+    Some((kind, keyword.start() as u64 + 1))
+}
+
+fn get_loops(function: &FunctionMetrics) -> Vec<LoopInfo> {
+    function
+        .branches
+        .iter()
+        .filter(|branch| {
+            branch.region_kind == RegionKind::Branch || branch.region_kind == RegionKind::MCDCBranch
+        })
+        .filter_map(|branch| {
+            let file_path = function.filenames[branch.file_id as usize];
+            let (kind, column) = detect_loop(file_path, branch)?;
+
+            let (entries, iterations) = match kind {
+                // each entry runs the body once, then the condition decides about another iteration
+                LoopKind::DoWhile => (
+                    branch.false_execution_count,
+                    branch.execution_count + branch.false_execution_count,
+                ),
+                _ => (
+                    get_enclosing_count(function, branch.file_id, branch.line_start, column),
+                    branch.execution_count,
+                ),
+            };
+
+            Some(LoopInfo {
+                function_name: function.name.to_string(),
+                file_path: file_path.to_string(),
+                kind,
+                line: branch.line_start,
+                column,
+                condition: get_file_part(
+                    file_path,
+                    branch.line_start,
+                    branch.column_start,
+                    branch.line_end,
+                    branch.column_end,
+                ),
+                entries,
+                iterations,
+                exits: branch.false_execution_count,
+                never_iterated_more_than_once: entries > 0 && iterations <= entries,
+                never_skipped: kind != LoopKind::DoWhile
+                    && entries > 0
+                    && branch.false_execution_count == 0,
+            })
+        })
+        .collect()
+}
+
+impl Analyzer for LoopAnalyzer {
+    fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
+        let mut total_loops = 0;
+        let mut flagged_loops = Vec::new();
+
+        for function in &coverage_report.data[0].functions {
+            if function.count == 0 {
+                continue;
+            }
+
+            for info in get_loops(function) {
+                total_loops += 1;
+                // loops that were never reached are part of the uncovered report
+                if info.never_iterated_more_than_once || info.never_skipped {
+                    flagged_loops.push(info);
+                }
+            }
+        }
+
+        self.results = Some(Output {
+            total_loops,
+            flagged_loops,
+        });
+        Ok(())
+    }
+
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
+        Ok(())
+    }
+
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        let data = self.results.as_ref().unwrap();
+        writeln!(writer, "Loop Iteration Report")?;
+        writeln!(writer, "=====================\n")?;
+        writeln!(
+            writer,
+            "Loops in executed functions: {}\n",
+            data.total_loops
+        )?;
+
+        let never_more_than_once: Vec<&LoopInfo> = data
+            .flagged_loops
+            .iter()
+            .filter(|l| l.never_iterated_more_than_once)
+            .collect();
+        let never_skipped: Vec<&LoopInfo> = data
+            .flagged_loops
+            .iter()
+            .filter(|l| l.never_skipped)
+            .collect();
+
+        write_loops(
+            "Never iterated more than once",
+            &never_more_than_once,
+            writer,
+        )?;
+        write_loops("Never skipped", &never_skipped, writer)?;
+        Ok(())
+    }
+}
+
+fn write_loops(title: &str, loops: &[&LoopInfo], writer: &mut dyn Write) -> io::Result<()> {
+    if loops.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "{}:", title)?;
+    writeln!(writer, "{}", "-".repeat(title.len() + 1))?;
+    for (i, info) in loops.iter().enumerate() {
+        let keyword = match info.kind {
+            LoopKind::For => "for",
+            LoopKind::While => "while",
+            LoopKind::DoWhile => "do/while",
+        };
+        writeln!(
+            writer,
+            "  {}. {} ({}) in {} ({}:{}:{}): {} entries, {} iterations",
+            i + 1,
+            keyword,
+            info.condition,
+            info.function_name,
+            info.file_path,
+            info.line,
+            info.column,
+            info.entries,
+            info.iterations
+        )?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
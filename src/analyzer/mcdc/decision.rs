@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+/// Structure of a decision, i.e., how its conditions are combined by `&&` and `||`.
+/// Conditions are identified by their index in source order.
+#[derive(Debug, PartialEq)]
+pub enum Expression {
+    Condition(usize),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Condition(usize),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+/// A test vector of a decision: the value of every condition (`None` if the condition
+/// was not evaluated due to short-circuiting) and the outcome of the decision.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestVector {
+    pub conditions: Vec<Option<bool>>,
+    pub result: bool,
+}
+
+/// Decisions with more conditions are not enumerated, since the number of test vectors
+/// grows exponentially.
+const MAX_CONDITIONS: usize = 16;
+
+impl Expression {
+    /// Parses the structure of a decision from its source text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The source text of the whole decision.
+    /// * `conditions` - The byte ranges of the conditions within `text`, in source order.
+    ///
+    /// # Returns
+    ///
+    /// The expression, or `None` if the text between the conditions can't be understood.
+    pub fn parse(text: &str, conditions: &[(usize, usize)]) -> Option<Expression> {
+        let tokens = tokenize(text, conditions)?;
+        let mut pos = 0;
+        let expression = parse_or(&tokens, &mut pos)?;
+        if pos == tokens.len() {
+            Some(expression)
+        } else {
+            None
+        }
+    }
+
+    /// Evaluates the decision with short-circuit semantics.
+    /// Conditions that are not evaluated stay `None` in `vector`.
+    fn evaluate(&self, assignment: u32, vector: &mut [Option<bool>]) -> bool {
+        match self {
+            Expression::Condition(index) => {
+                let value = assignment & (1 << index) != 0;
+                vector[*index] = Some(value);
+                value
+            }
+            Expression::And(lhs, rhs) => {
+                lhs.evaluate(assignment, vector) && rhs.evaluate(assignment, vector)
+            }
+            Expression::Or(lhs, rhs) => {
+                lhs.evaluate(assignment, vector) || rhs.evaluate(assignment, vector)
+            }
+        }
+    }
+
+    /// Enumerates all distinct test vectors of a decision with `num_conditions` conditions.
+    pub fn test_vectors(&self, num_conditions: usize) -> Vec<TestVector> {
+        let mut vectors: Vec<TestVector> = Vec::new();
+        if num_conditions > MAX_CONDITIONS {
+            return vectors;
+        }
+
+        // assignments that differ only in conditions that are not evaluated give the same vector
+        let mut seen = HashSet::new();
+        for assignment in 0..(1u32 << num_conditions) {
+            let mut conditions = vec![None; num_conditions];
+            let result = self.evaluate(assignment, &mut conditions);
+            let vector = TestVector { conditions, result };
+            if seen.insert(vector.clone()) {
+                vectors.push(vector);
+            }
+        }
+
+        vectors
+    }
+}
+
+/// Returns all pairs of test vectors that show that `condition` independently affects the decision,
+/// following the definition used by llvm-cov: both vectors evaluate the condition to different
+/// values, the outcomes differ, and every other condition is either equal or not evaluated in one of them.
+pub fn independence_pairs(
+    vectors: &[TestVector],
+    condition: usize,
+) -> Vec<(TestVector, TestVector)> {
+    let mut pairs = Vec::new();
+
+    for (i, a) in vectors.iter().enumerate() {
+        for b in &vectors[i + 1..] {
+            let differs = matches!(
+                (a.conditions[condition], b.conditions[condition]),
+                (Some(x), Some(y)) if x != y
+            );
+            let others_match = a
+                .conditions
+                .iter()
+                .zip(&b.conditions)
+                .enumerate()
+                .filter(|(k, _)| *k != condition)
+                .all(|(_, (x, y))| x.is_none() || y.is_none() || x == y);
+
+            if differs && others_match && a.result != b.result {
+                // keep the vector where the condition is true first
+                if a.conditions[condition] == Some(true) {
+                    pairs.push((a.clone(), b.clone()));
+                } else {
+                    pairs.push((b.clone(), a.clone()));
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Splits the decision text into condition placeholders, logical operators and parentheses.
+/// Whitespace and negations outside of conditions are skipped, as they don't change
+/// which conditions affect the decision.
+fn tokenize(text: &str, conditions: &[(usize, usize)]) -> Option<Vec<Token>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut next_condition = 0;
+
+    while pos < bytes.len() {
+        if let Some(&(start, end)) = conditions.get(next_condition) {
+            if pos == start {
+                tokens.push(Token::Condition(next_condition));
+                next_condition += 1;
+                pos = end;
+                continue;
+            }
+        }
+
+        match bytes[pos] {
+            b'&' if bytes.get(pos + 1) == Some(&b'&') => {
+                tokens.push(Token::And);
+                pos += 2;
+            }
+            b'|' if bytes.get(pos + 1) == Some(&b'|') => {
+                tokens.push(Token::Or);
+                pos += 2;
+            }
+            b'(' => {
+                tokens.push(Token::Open);
+                pos += 1;
+            }
+            b')' => {
+                tokens.push(Token::Close);
+                pos += 1;
+            }
+            b'!' => pos += 1,
+            c if c.is_ascii_whitespace() => pos += 1,
+            _ => return None,
+        }
+    }
+
+    if next_condition == conditions.len() {
+        Some(tokens)
+    } else {
+        None
+    }
+}
+
+fn parse_or(tokens: &[Token], pos: &mut usize) -> Option<Expression> {
+    let mut lhs = parse_and(tokens, pos)?;
+    while tokens.get(*pos) == Some(&Token::Or) {
+        *pos += 1;
+        let rhs = parse_and(tokens, pos)?;
+        lhs = Expression::Or(Box::new(lhs), Box::new(rhs));
+    }
+    Some(lhs)
+}
+
+fn parse_and(tokens: &[Token], pos: &mut usize) -> Option<Expression> {
+    let mut lhs = parse_primary(tokens, pos)?;
+    while tokens.get(*pos) == Some(&Token::And) {
+        *pos += 1;
+        let rhs = parse_primary(tokens, pos)?;
+        lhs = Expression::And(Box::new(lhs), Box::new(rhs));
+    }
+    Some(lhs)
+}
+
+fn parse_primary(tokens: &[Token], pos: &mut usize) -> Option<Expression> {
+    match tokens.get(*pos)? {
+        Token::Condition(index) => {
+            *pos += 1;
+            Some(Expression::Condition(*index))
+        }
+        Token::Open => {
+            *pos += 1;
+            let expression = parse_or(tokens, pos)?;
+            if tokens.get(*pos) != Some(&Token::Close) {
+                return None;
+            }
+            *pos += 1;
+            Some(expression)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,364 @@
+use super::decision::{independence_pairs, Expression, TestVector};
+use crate::analyzer::uncovered::get_file_part;
+use crate::analyzer::Analyzer;
+use crate::error::GetCovError;
+use llvm_cov_json::{Branch, CoverageReport, FunctionMetrics, MCDCRecord, RegionKind};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// A test vector of a decision, as part of a missing independence pair.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestVectorInfo {
+    /// The value of each condition, `None` if it is not evaluated due to short-circuiting.
+    pub conditions: Vec<Option<bool>>,
+    pub result: bool,
+    /// False if the branch counts prove that this vector was never executed, i.e., one of
+    /// its condition values was never observed.
+    pub possibly_executed: bool,
+}
+
+/// Two test vectors that would show that a condition independently affects its decision.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndependencePair {
+    pub condition_true: TestVectorInfo,
+    pub condition_false: TestVectorInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UncoveredCondition {
+    /// 1-based index of the condition within the decision, as in `llvm-cov show` (C1, C2, ...).
+    pub index: usize,
+    pub text: String,
+    pub start_line: u64,
+    pub start_column: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+    pub true_count: u64,
+    pub false_count: u64,
+    /// Any of these pairs would cover the condition.
+    /// Empty if the structure of the decision could not be determined.
+    pub missing_pairs: Vec<IndependencePair>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct McdcDecision {
+    pub file_path: String,
+    pub start_line: u64,
+    pub start_column: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+    pub text: String,
+    pub num_conditions: usize,
+    pub uncovered_conditions: Vec<UncoveredCondition>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct McdcFunction {
+    pub function_name: String,
+    pub file_path: String,
+    pub decisions: Vec<McdcDecision>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Output {
+    covered_decisions: u64,
+    total_decisions: u64,
+    covered_conditions: u64,
+    total_conditions: u64,
+    functions: Vec<McdcFunction>,
+}
+
+/// Reports the conditions of MC/DC decisions that were never shown to independently affect
+/// their decision, together with the test vector pairs that would do so.
+/// Requires a binary built with `-fcoverage-mcdc`.
+pub struct McdcAnalyzer {
+    results: Option<Output>,
+}
+
+impl McdcAnalyzer {
+    pub fn new() -> Self {
+        Self { results: None }
+    }
+}
+
+/// Translates a line/column position into a byte offset within a snippet starting at `start`.
+fn offset_in_snippet(snippet: &str, start: (u64, u64), position: (u64, u64)) -> Option<usize> {
+    let line_index = position.0.checked_sub(start.0)? as usize;
+    let mut offset = 0;
+    for line in snippet.split('\n').take(line_index) {
+        offset += line.len() + 1;
+    }
+
+    let line_start_column = if line_index == 0 { start.1 } else { 1 };
+    Some(offset + position.1.checked_sub(line_start_column)? as usize)
+}
+
+/// Finds the condition branches of a decision, in source order. Only the branches expanded
+/// into the same file as the decision are considered, and as a decision in a macro use can
+/// overlap the lines of branches in another file, the conditions have to come from one file,
+/// preferring the file with as many branches as the decision has conditions.
+fn get_condition_branches<'a>(
+    function: &'a FunctionMetrics,
+    record: &MCDCRecord,
+) -> Vec<&'a Branch> {
+    let mut by_file: BTreeMap<u64, Vec<&Branch>> = BTreeMap::new();
+    for branch in function.branches.iter().filter(|branch| {
+        branch.region_kind == RegionKind::MCDCBranch
+            && branch.expanded_file_id == record.expanded_file_id
+            && (branch.line_start, branch.column_start) >= (record.line_start, record.column_start)
+            && (branch.line_end, branch.column_end) <= (record.line_end, record.column_end)
+    }) {
+        by_file.entry(branch.file_id).or_default().push(branch);
+    }
+
+    let Some(file_id) = by_file
+        .iter()
+        .find(|(_, branches)| branches.len() == record.conditions.len())
+        .or_else(|| by_file.iter().next())
+        .map(|(file_id, _)| *file_id)
+    else {
+        return Vec::new();
+    };
+    let mut branches = by_file.remove(&file_id).unwrap_or_default();
+    branches.sort_by_key(|branch| (branch.line_start, branch.column_start));
+    branches
+}
+
+fn to_test_vector_info(vector: &TestVector, branches: &[&Branch]) -> TestVectorInfo {
+    let possibly_executed =
+        vector
+            .conditions
+            .iter()
+            .zip(branches)
+            .all(|(value, branch)| match value {
+                Some(true) => branch.execution_count > 0,
+                Some(false) => branch.false_execution_count > 0,
+                None => true,
+            });
+
+    TestVectorInfo {
+        conditions: vector.conditions.clone(),
+        result: vector.result,
+        possibly_executed,
+    }
+}
+
+fn analyze_decision(function: &FunctionMetrics, record: &MCDCRecord) -> Option<McdcDecision> {
+    let branches = get_condition_branches(function, record);
+    if branches.is_empty() || branches.len() != record.conditions.len() {
+        warn!(
+            "Can't match the conditions of the decision at {}:{} in {}",
+            record.line_start, record.column_start, function.name
+        );
+        return None;
+    }
+
+    let file_path = function.filenames[branches[0].file_id as usize];
+    let text = get_file_part(
+        file_path,
+        record.line_start,
+        record.column_start,
+        record.line_end,
+        record.column_end,
+    );
+
+    // recover how the conditions are combined, so that we can enumerate the test vectors
+    let start = (record.line_start, record.column_start);
+    let condition_ranges: Option<Vec<(usize, usize)>> = branches
+        .iter()
+        .map(|branch| {
+            Some((
+                offset_in_snippet(&text, start, (branch.line_start, branch.column_start))?,
+                offset_in_snippet(&text, start, (branch.line_end, branch.column_end))?,
+            ))
+        })
+        .collect();
+    let vectors = condition_ranges
+        .and_then(|ranges| Expression::parse(&text, &ranges))
+        .map(|expression| expression.test_vectors(branches.len()))
+        .unwrap_or_default();
+
+    let uncovered_conditions = record
+        .conditions
+        .iter()
+        .enumerate()
+        .filter(|(_, covered)| !**covered)
+        .map(|(index, _)| {
+            let branch = branches[index];
+            UncoveredCondition {
+                index: index + 1,
+                text: get_file_part(
+                    file_path,
+                    branch.line_start,
+                    branch.column_start,
+                    branch.line_end,
+                    branch.column_end,
+                ),
+                start_line: branch.line_start,
+                start_column: branch.column_start,
+                end_line: branch.line_end,
+                end_column: branch.column_end,
+                true_count: branch.execution_count,
+                false_count: branch.false_execution_count,
+                missing_pairs: independence_pairs(&vectors, index)
+                    .iter()
+                    .map(|(condition_true, condition_false)| IndependencePair {
+                        condition_true: to_test_vector_info(condition_true, &branches),
+                        condition_false: to_test_vector_info(condition_false, &branches),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Some(McdcDecision {
+        file_path: file_path.to_string(),
+        start_line: record.line_start,
+        start_column: record.column_start,
+        end_line: record.line_end,
+        end_column: record.column_end,
+        text,
+        num_conditions: record.conditions.len(),
+        uncovered_conditions,
+    })
+}
+
+impl Analyzer for McdcAnalyzer {
+    fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
+        let mut output = Output {
+            covered_decisions: 0,
+            total_decisions: 0,
+            covered_conditions: 0,
+            total_conditions: 0,
+            functions: Vec::new(),
+        };
+
+        for function in &coverage_report.data[0].functions {
+            let Some(records) = &function.mcdc_records else {
+                continue;
+            };
+
+            for record in records {
+                output.total_decisions += 1;
+                output.total_conditions += record.conditions.len() as u64;
+                output.covered_conditions +=
+                    record.conditions.iter().filter(|c| **c).count() as u64;
+                if record.is_covered() {
+                    output.covered_decisions += 1;
+                }
+            }
+
+            // like the uncovered analysis, only report functions that are reached at all
+            if function.count == 0 {
+                continue;
+            }
+
+            let decisions: Vec<McdcDecision> = records
+                .iter()
+                .filter(|record| !record.is_covered())
+                .filter_map(|record| analyze_decision(function, record))
+                .collect();
+
+            if let (false, Some(first)) = (decisions.is_empty(), function.regions.first()) {
+                output.functions.push(McdcFunction {
+                    function_name: function.name.to_string(),
+                    file_path: function.filenames[first.file_id as usize].to_string(),
+                    decisions,
+                });
+            }
+        }
+
+        self.results = Some(output);
+        Ok(())
+    }
+
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
+        Ok(())
+    }
+
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        let data = self.results.as_ref().unwrap();
+        writeln!(writer, "MC/DC Coverage Report")?;
+        writeln!(writer, "=====================\n")?;
+        writeln!(
+            writer,
+            "Decisions: {}/{} fully covered, Conditions: {}/{} covered\n",
+            data.covered_decisions,
+            data.total_decisions,
+            data.covered_conditions,
+            data.total_conditions
+        )?;
+
+        if data.total_decisions == 0 {
+            writeln!(
+                writer,
+                "No MC/DC records found. Was the binary built with -fcoverage-mcdc?"
+            )?;
+            return Ok(());
+        }
+
+        for function in &data.functions {
+            writeln!(writer, "Function: {}", function.function_name)?;
+            writeln!(writer, "Location: {}", function.file_path)?;
+
+            for decision in &function.decisions {
+                writeln!(
+                    writer,
+                    "\n  Decision ({}:{}:{} - {}:{}): {}",
+                    decision.file_path,
+                    decision.start_line,
+                    decision.start_column,
+                    decision.end_line,
+                    decision.end_column,
+                    decision.text
+                )?;
+
+                for condition in &decision.uncovered_conditions {
+                    writeln!(
+                        writer,
+                        "    C{}: {} (true: {}, false: {}) is not shown to independently affect the decision",
+                        condition.index, condition.text, condition.true_count, condition.false_count
+                    )?;
+                    for pair in &condition.missing_pairs {
+                        writeln!(
+                            writer,
+                            "      missing pair: {}  /  {}",
+                            format_test_vector(&pair.condition_true),
+                            format_test_vector(&pair.condition_false)
+                        )?;
+                    }
+                }
+            }
+
+            writeln!(writer, "\n---------------------------------\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a test vector like `llvm-cov show` does, e.g. `{ T, -, F = F }`.
+fn format_test_vector(vector: &TestVectorInfo) -> String {
+    let conditions: Vec<&str> = vector
+        .conditions
+        .iter()
+        .map(|value| match value {
+            Some(true) => "T",
+            Some(false) => "F",
+            None => "-",
+        })
+        .collect();
+
+    format!(
+        "{{ {} = {} }}{}",
+        conditions.join(", "),
+        if vector.result { "T" } else { "F" },
+        if vector.possibly_executed {
+            ""
+        } else {
+            " (never executed)"
+        }
+    )
+}
@@ -0,0 +1,4 @@
+mod decision;
+mod mcdc_analyzer;
+
+pub use mcdc_analyzer::McdcAnalyzer;
@@ -1,14 +1,66 @@
+mod biased_branches;
+mod binaries;
+mod dictionary;
 mod extract_functions;
+mod function_report;
+mod line_coverage;
+mod loops;
+mod mcdc;
+mod switches;
+mod tokenizer;
 mod uncovered;
 
-pub use extract_functions::ExtractFunctionsAnalyzer;
-pub use uncovered::UncoveredAnalyzer;
+pub use binaries::get_binary_coverage;
+pub use dictionary::DictionaryAnalyzer;
+pub use extract_functions::{ExtractFunctionsAnalyzer, FunctionList};
+pub use function_report::FunctionReportAnalyzer;
+pub use line_coverage::{LineCoverageAnalyzer, LineCoverageMap};
+pub use loops::LoopAnalyzer;
+pub use mcdc::McdcAnalyzer;
+pub use switches::SwitchAnalyzer;
+pub use tokenizer::{tokenize, TokenKind};
+pub use uncovered::{
+    get_file_part, get_uncovered, get_uncovered_regions, CodeRegion, Coverage, ExpansionSite,
+    PartiallyCoveredFunction, PartiallyCoveredPredicate, RegionCategory, RegionCategoryFilter,
+    UncoveredAnalyzer, UncoveredOutput,
+};
 
+use crate::config::{MarkdownOptions, PromptOptions};
 use crate::error::GetCovError;
 use llvm_cov_json::CoverageReport;
+use std::io::Write;
 
 pub trait Analyzer {
     fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError>;
-    fn output_json(&self) -> Result<(), GetCovError>;
-    fn output_text(&self);
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError>;
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError>;
+
+    /// Writes the findings as a SARIF log, which only the uncovered analysis supports.
+    fn output_sarif(&self, _writer: &mut dyn Write) -> Result<(), GetCovError> {
+        Err(GetCovError::ArgParse(
+            "SARIF output is only supported by the uncovered analysis".into(),
+        ))
+    }
+
+    /// Writes a markdown summary for code review, which only the uncovered analysis supports.
+    fn output_markdown(
+        &self,
+        _options: &MarkdownOptions,
+        _writer: &mut dyn Write,
+    ) -> Result<(), GetCovError> {
+        Err(GetCovError::ArgParse(
+            "Markdown output is only supported by the uncovered analysis".into(),
+        ))
+    }
+
+    /// Writes context units for an LLM, which only the uncovered analysis supports.
+    fn output_prompt(
+        &self,
+        _options: &PromptOptions,
+        _writer: &mut dyn Write,
+    ) -> Result<(), GetCovError> {
+        Err(GetCovError::ArgParse(
+            "Prompt output is only supported by the uncovered analysis".into(),
+        ))
+    }
 }
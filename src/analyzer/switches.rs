@@ -0,0 +1,332 @@
+use super::Analyzer;
+use crate::analyzer::loops::get_enclosing_count;
+use crate::analyzer::uncovered::get_file_lines;
+use crate::error::GetCovError;
+use llvm_cov_json::{Branch, CoverageReport, FunctionMetrics, RegionKind};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::LazyLock;
+
+static SWITCH_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bswitch\s*\(").unwrap());
+static LABEL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(case\b.*|default)\s*:?$").unwrap());
+
+/// A `case` or `default` label and how often the switch jumped to it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SwitchCase {
+    pub label: String,
+    pub line: u64,
+    pub column: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SwitchInfo {
+    pub function_name: String,
+    pub file_path: String,
+    /// Position of the `switch` keyword.
+    pub line: u64,
+    pub column: u64,
+    pub condition: String,
+    /// Number of times the switch was executed.
+    pub entries: u64,
+    pub cases: Vec<SwitchCase>,
+    /// Labels of the cases that were never taken.
+    pub never_taken: Vec<String>,
+    /// If the switch has no `default` label, the number of times no case matched.
+    pub implicit_default_count: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Output {
+    total_switches: u64,
+    incomplete_switches: Vec<SwitchInfo>,
+}
+
+/// Reports the `case` and `default` labels of switch statements that were never taken,
+/// together with the hit count of every label.
+/// Clang emits one branch region per label; the switch itself is found in the source.
+pub struct SwitchAnalyzer {
+    results: Option<Output>,
+}
+
+impl SwitchAnalyzer {
+    pub fn new() -> Self {
+        Self { results: None }
+    }
+}
+
+type Position = (usize, usize); // 0-based line and byte column
+
+/// A `switch` statement found in the source.
+struct SwitchStatement {
+    keyword: Position,
+    condition: String,
+    body_start: Position,
+    body_end: Position,
+}
+
+/// Scans the source starting at `start`, skipping comments and literals,
+/// and returns the position of the first `target` character on nesting level zero.
+/// `open` and `close` increase and decrease the nesting level.
+fn find_balanced(
+    lines: &[String],
+    start: Position,
+    open: u8,
+    close: u8,
+    target: u8,
+) -> Option<Position> {
+    let mut depth = 0i64;
+    let mut in_block_comment = false;
+
+    for (line_index, line) in lines.iter().enumerate().skip(start.0) {
+        let bytes = line.as_bytes();
+        let mut i = if line_index == start.0 { start.1 } else { 0 };
+
+        while i < bytes.len() {
+            if in_block_comment {
+                if bytes[i..].starts_with(b"*/") {
+                    in_block_comment = false;
+                    i += 1;
+                }
+                i += 1;
+                continue;
+            }
+
+            match bytes[i] {
+                b'/' if bytes.get(i + 1) == Some(&b'/') => break,
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    in_block_comment = true;
+                    i += 1;
+                }
+                quote @ (b'"' | b'\'') => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != quote {
+                        if bytes[i] == b'\\' {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                c if c == target && depth == 0 => return Some((line_index, i)),
+                c if c == open => depth += 1,
+                c if c == close => {
+                    depth -= 1;
+                    if c == target && depth == 0 {
+                        return Some((line_index, i));
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    None
+}
+
+/// Finds the switch statements between `start_line` and `end_line` (1-based, inclusive).
+fn find_switches(lines: &[String], start_line: u64, end_line: u64) -> Vec<SwitchStatement> {
+    let mut switches = Vec::new();
+
+    let first = (start_line as usize).saturating_sub(1);
+    let last = (end_line as usize).min(lines.len());
+    for line_index in first..last {
+        for keyword in SWITCH_RE.find_iter(&lines[line_index]) {
+            // the condition ends at the matching parenthesis, the body is the following block
+            let open_paren = (line_index, keyword.end() - 1);
+            let Some(close_paren) = find_balanced(lines, open_paren, b'(', b')', b')') else {
+                continue;
+            };
+            let Some(body_start) =
+                find_balanced(lines, (close_paren.0, close_paren.1 + 1), b'(', b')', b'{')
+            else {
+                continue;
+            };
+            let Some(body_end) = find_balanced(lines, body_start, b'{', b'}', b'}') else {
+                continue;
+            };
+
+            let condition = if open_paren.0 == close_paren.0 {
+                lines[open_paren.0][open_paren.1 + 1..close_paren.1].to_string()
+            } else {
+                let mut condition = lines[open_paren.0][open_paren.1 + 1..].to_string();
+                for line in &lines[open_paren.0 + 1..close_paren.0] {
+                    condition.push('\n');
+                    condition.push_str(line);
+                }
+                condition.push('\n');
+                condition.push_str(&lines[close_paren.0][..close_paren.1]);
+                condition
+            };
+
+            switches.push(SwitchStatement {
+                keyword: (line_index, keyword.start()),
+                condition: condition.trim().to_string(),
+                body_start,
+                body_end,
+            });
+        }
+    }
+
+    switches
+}
+
+/// Returns the label text (e.g. `case FOO` or `default`) if the branch is a switch case.
+fn get_case_label(lines: &[String], branch: &Branch) -> Option<String> {
+    let line = lines.get((branch.line_start as usize).saturating_sub(1))?;
+    let start = (branch.column_start as usize).saturating_sub(1);
+    let end = if branch.line_end == branch.line_start {
+        (branch.column_end as usize).saturating_sub(1)
+    } else {
+        line.len()
+    };
+    let text = line.get(start..end)?.trim();
+    let captures = LABEL_RE.captures(text)?;
+    Some(captures[1].trim_end_matches(':').trim().to_string())
+}
+
+fn get_switches(function: &FunctionMetrics) -> Vec<SwitchInfo> {
+    let Some(first_region) = function.regions.first() else {
+        return Vec::new();
+    };
+    let file_id = first_region.file_id;
+    let file_path = function.filenames[file_id as usize];
+    let lines = get_file_lines(file_path);
+
+    let case_branches: Vec<(&Branch, String)> = function
+        .branches
+        .iter()
+        .filter(|branch| branch.file_id == file_id && branch.region_kind == RegionKind::Branch)
+        .filter_map(|branch| Some((branch, get_case_label(&lines, branch)?)))
+        .collect();
+    if case_branches.is_empty() {
+        return Vec::new();
+    }
+
+    let statements = find_switches(&lines, first_region.line_start, first_region.line_end);
+    let mut switches: Vec<SwitchInfo> = statements
+        .iter()
+        .map(|statement| {
+            let line = statement.keyword.0 as u64 + 1;
+            let column = statement.keyword.1 as u64 + 1;
+            SwitchInfo {
+                function_name: function.name.to_string(),
+                file_path: file_path.to_string(),
+                line,
+                column,
+                condition: statement.condition.clone(),
+                entries: get_enclosing_count(function, file_id, line, column),
+                cases: Vec::new(),
+                never_taken: Vec::new(),
+                implicit_default_count: None,
+            }
+        })
+        .collect();
+
+    for (branch, label) in case_branches {
+        let position = (
+            branch.line_start as usize - 1,
+            branch.column_start as usize - 1,
+        );
+        // the innermost switch whose body contains the label
+        let owner = statements
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.body_start < position && position < s.body_end)
+            .max_by_key(|(_, s)| s.body_start)
+            .map(|(index, _)| index);
+
+        if let Some(index) = owner {
+            switches[index].cases.push(SwitchCase {
+                label,
+                line: branch.line_start,
+                column: branch.column_start,
+                count: branch.execution_count,
+            });
+        }
+    }
+
+    for switch in &mut switches {
+        switch.never_taken = switch
+            .cases
+            .iter()
+            .filter(|case| case.count == 0)
+            .map(|case| case.label.clone())
+            .collect();
+        if !switch.cases.iter().any(|case| case.label == "default") {
+            let taken: u64 = switch.cases.iter().map(|case| case.count).sum();
+            switch.implicit_default_count = Some(switch.entries.saturating_sub(taken));
+        }
+    }
+
+    switches.retain(|switch| !switch.cases.is_empty());
+    switches
+}
+
+impl Analyzer for SwitchAnalyzer {
+    fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
+        let mut total_switches = 0;
+        let mut incomplete_switches = Vec::new();
+
+        for function in &coverage_report.data[0].functions {
+            // switches in functions that were never called are part of the uncovered report
+            if function.count == 0 {
+                continue;
+            }
+
+            for switch in get_switches(function) {
+                total_switches += 1;
+                if switch.entries > 0 && !switch.never_taken.is_empty() {
+                    incomplete_switches.push(switch);
+                }
+            }
+        }
+
+        self.results = Some(Output {
+            total_switches,
+            incomplete_switches,
+        });
+        Ok(())
+    }
+
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
+        Ok(())
+    }
+
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        let data = self.results.as_ref().unwrap();
+        writeln!(writer, "Switch Coverage Report")?;
+        writeln!(writer, "======================\n")?;
+        writeln!(
+            writer,
+            "{} of {} switches in executed functions have cases that were never taken.\n",
+            data.incomplete_switches.len(),
+            data.total_switches
+        )?;
+
+        for switch in &data.incomplete_switches {
+            writeln!(
+                writer,
+                "switch ({}) in {} ({}:{}:{}), executed {} times",
+                switch.condition,
+                switch.function_name,
+                switch.file_path,
+                switch.line,
+                switch.column,
+                switch.entries
+            )?;
+            writeln!(writer, "  Never taken: {}", switch.never_taken.join(", "))?;
+            for case in &switch.cases {
+                writeln!(writer, "    {:>10}  {}", case.count, case.label)?;
+            }
+            if let Some(count) = switch.implicit_default_count {
+                writeln!(writer, "    {:>10}  (no case matched)", count)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+//! A small C/C++ tokenizer, good enough to tell code from comments, literals and
+//! preprocessor lines. It handles line continuations, character and string literals
+//! (including encoding prefixes and C++ raw strings) and comment markers inside them.
+//! Punctuators are returned one character at a time.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Identifier,
+    Number,
+    String,
+    Char,
+    Punctuator,
+    Comment,
+    /// A whole preprocessor directive, including its continuation lines.
+    Preprocessor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offsets into the source, `end` is exclusive.
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token<'_> {
+    /// Checks if the token does something when run. Comments, preprocessor directives,
+    /// braces and empty statements don't.
+    pub fn is_executable(&self) -> bool {
+        match self.kind {
+            TokenKind::Comment | TokenKind::Preprocessor => false,
+            TokenKind::Punctuator => !matches!(self.text, "{" | "}" | ";"),
+            _ => true,
+        }
+    }
+}
+
+const DIRECTIVES: [&str; 13] = [
+    "define", "undef", "include", "if", "ifdef", "ifndef", "elif", "else", "endif", "pragma",
+    "error", "warning", "line",
+];
+
+/// Returns the length of a line continuation (backslash and line break) at `i`, or 0.
+fn continuation_len(bytes: &[u8], i: usize) -> usize {
+    match (bytes.get(i), bytes.get(i + 1), bytes.get(i + 2)) {
+        (Some(b'\\'), Some(b'\n'), _) => 2,
+        (Some(b'\\'), Some(b'\r'), Some(b'\n')) => 3,
+        _ => 0,
+    }
+}
+
+/// Returns the end of the line starting at `i`, following line continuations.
+fn end_of_logical_line(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i] != b'\n' {
+        let continuation = continuation_len(bytes, i);
+        i += if continuation > 0 { continuation } else { 1 };
+    }
+    i
+}
+
+/// Checks if the `#` at `i` starts a preprocessor directive: it is the first token on its line
+/// and followed by a directive name, which tells it apart from the `#` operator in macro bodies.
+fn is_directive(source: &str, i: usize) -> bool {
+    let line_start = source[..i].rfind('\n').map_or(0, |pos| pos + 1);
+    if !source[line_start..i].trim().is_empty() {
+        return false;
+    }
+
+    let rest = source[i + 1..].trim_start_matches([' ', '\t']);
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+    name_len == 0 || DIRECTIVES.contains(&&rest[..name_len])
+}
+
+/// Returns the end of a quoted literal whose opening quote is at `i`.
+/// Unterminated literals end at the end of the line.
+fn end_of_quoted(bytes: &[u8], i: usize) -> usize {
+    let quote = bytes[i];
+    let mut j = i + 1;
+    while j < bytes.len() && bytes[j] != b'\n' {
+        match bytes[j] {
+            b'\\' => j += 2,
+            c if c == quote => return j + 1,
+            _ => j += 1,
+        }
+    }
+    j.min(bytes.len())
+}
+
+/// Returns the end of a C++ raw string whose opening quote is at `i`, i.e., `R"delim(...)delim"`.
+fn end_of_raw_string(source: &str, i: usize) -> usize {
+    let Some(paren) = source[i + 1..].find(['(', '\n']).map(|pos| i + 1 + pos) else {
+        return source.len();
+    };
+    if source.as_bytes()[paren] != b'(' {
+        return paren;
+    }
+
+    let terminator = format!("){}\"", &source[i + 1..paren]);
+    source[paren + 1..]
+        .find(&terminator)
+        .map_or(source.len(), |pos| paren + 1 + pos + terminator.len())
+}
+
+/// Splits C/C++ source into tokens, skipping whitespace and line continuations.
+pub fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let continuation = continuation_len(bytes, i);
+        if continuation > 0 {
+            i += continuation;
+            continue;
+        }
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let kind = match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = end_of_logical_line(bytes, i);
+                TokenKind::Comment
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = source[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |pos| i + 2 + pos + 2);
+                TokenKind::Comment
+            }
+            b'#' if is_directive(source, i) => {
+                i = end_of_logical_line(bytes, i);
+                TokenKind::Preprocessor
+            }
+            b'"' => {
+                i = end_of_quoted(bytes, i);
+                TokenKind::String
+            }
+            b'\'' => {
+                i = end_of_quoted(bytes, i);
+                TokenKind::Char
+            }
+            c if c.is_ascii_digit()
+                || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) =>
+            {
+                i += 1;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'+' | b'-' if matches!(bytes[i - 1], b'e' | b'E' | b'p' | b'P') => i += 1,
+                        c if c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || c == b'\'' => {
+                            i += 1
+                        }
+                        _ => break,
+                    }
+                }
+                TokenKind::Number
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || !c.is_ascii() => {
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || bytes[i] == b'_'
+                        || !bytes[i].is_ascii())
+                {
+                    i += 1;
+                }
+
+                // encoding prefixes like `u8"..."`, `L'x'` and raw strings like `R"(...)"`
+                match (&source[start..i], bytes.get(i)) {
+                    ("u8" | "u" | "U" | "L", Some(b'"')) => {
+                        i = end_of_quoted(bytes, i);
+                        TokenKind::String
+                    }
+                    ("u8" | "u" | "U" | "L", Some(b'\'')) => {
+                        i = end_of_quoted(bytes, i);
+                        TokenKind::Char
+                    }
+                    ("R" | "u8R" | "uR" | "UR" | "LR", Some(b'"')) => {
+                        i = end_of_raw_string(source, i);
+                        TokenKind::String
+                    }
+                    _ => TokenKind::Identifier,
+                }
+            }
+            _ => {
+                i += source[i..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Punctuator
+            }
+        };
+
+        let end = i.min(bytes.len());
+        tokens.push(Token {
+            kind,
+            text: &source[start..end],
+            start,
+            end,
+        });
+        i = end;
+    }
+
+    tokens
+}
+
+/// Checks if the source contains anything besides comments, preprocessor lines, braces and `;`.
+pub fn has_executable_tokens(source: &str) -> bool {
+    tokenize(source).iter().any(Token::is_executable)
+}
+
+/// Removes the comments from the source, keeping everything else as it is.
+/// A comment is replaced by a space, so the tokens around it stay apart.
+pub fn strip_comments(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut last_end = 0;
+
+    for token in tokenize(source) {
+        if token.kind == TokenKind::Comment {
+            result.push_str(&source[last_end..token.start]);
+            result.push(' ');
+            last_end = token.end;
+        }
+    }
+    result.push_str(&source[last_end..]);
+    result
+}
+
+/// Trims comments and whitespace from both ends of the source.
+pub fn trim_snippet(source: &str) -> &str {
+    let tokens = tokenize(source);
+    let mut code = tokens.iter().filter(|t| t.kind != TokenKind::Comment);
+
+    match (code.next(), code.next_back()) {
+        (Some(first), Some(last)) => &source[first.start..last.end],
+        (Some(only), None) => &source[only.start..only.end],
+        _ => "",
+    }
+}
@@ -0,0 +1,182 @@
+use regex::Regex;
+use schemars::JsonSchema;
+use std::sync::LazyLock;
+
+use super::model::CodeRegion;
+use super::report::{get_file_line, get_file_part};
+use crate::analyzer::tokenizer::strip_comments;
+
+/// What an uncovered region most likely does, guessed from its source text.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum RegionCategory {
+    /// Bails out on an error, e.g. `goto err`, `return -1`, `perror(..)`, `exit(1)` or `abort()`.
+    ErrorHandling,
+    /// Only prints or logs something.
+    Logging,
+    /// Part of an assertion, e.g. `assert(..)` or `__builtin_unreachable()`.
+    Assertion,
+    /// Handles a failed allocation, i.e., a `NULL` check right after `malloc` and friends.
+    AllocationFailure,
+    /// Everything else.
+    Functional,
+}
+
+impl RegionCategory {
+    pub const NAMES: [&'static str; 5] = [
+        "error-handling",
+        "logging",
+        "assertion",
+        "allocation-failure",
+        "functional",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegionCategory::ErrorHandling => "error-handling",
+            RegionCategory::Logging => "logging",
+            RegionCategory::Assertion => "assertion",
+            RegionCategory::AllocationFailure => "allocation-failure",
+            RegionCategory::Functional => "functional",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "error-handling" => Some(RegionCategory::ErrorHandling),
+            "logging" => Some(RegionCategory::Logging),
+            "assertion" => Some(RegionCategory::Assertion),
+            "allocation-failure" => Some(RegionCategory::AllocationFailure),
+            "functional" => Some(RegionCategory::Functional),
+            _ => None,
+        }
+    }
+}
+
+/// The categories to leave out of the report, and the ones to list after the others.
+#[derive(Debug, Clone, Default)]
+pub struct RegionCategoryFilter {
+    pub excluded: Vec<RegionCategory>,
+    pub downweighted: Vec<RegionCategory>,
+}
+
+/// How many lines above a `NULL` check are searched for the allocation of the checked pointer.
+const ALLOCATION_LOOKBACK: u64 = 10;
+
+static NULL_CHECK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"!\s*([A-Za-z_]\w*)\s*\)|\b([A-Za-z_]\w*)\s*==\s*(?:NULL|nullptr)\b|\b(?:NULL|nullptr)\s*==\s*([A-Za-z_]\w*)",
+    )
+    .unwrap()
+});
+/// An assignment of an allocation, capturing the assigned name.
+static ALLOCATION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b([A-Za-z_]\w*)\s*=[^;=]*\b(?:\w*alloc\w*|strn?dup|new)\b").unwrap()
+});
+static LOGGING_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:f?printf|f?puts|putchar|perror|syslog|std::cout|std::cerr|cout|cerr|\w*(?:log|LOG|debug|DEBUG|trace|TRACE)\w*)\b",
+    )
+    .unwrap()
+});
+static ASSERTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(?:assert|static_assert|_Static_assert|__assert_fail|__assert_rtn|__builtin_unreachable|unreachable|BUG_ON|BUG)\b",
+    )
+    .unwrap()
+});
+/// Exits only count with a failure status, `exit(0)` is a normal way to end the program.
+static ERROR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\bgoto\s+\w*(?:err|fail|out|cleanup|bail|exit|error|abort)\w*|\breturn\s*\(?\s*-\s*\d|\breturn\s*\(?\s*-?E[A-Z]+\b|\breturn\s+(?:NULL|nullptr)\b|\b(?:perror|abort|err|errx)\s*\(|\b(?:exit|_exit|_Exit)\s*\(\s*(?:EXIT_FAILURE|-?\s*[1-9]\d*)\s*\)|\bthrow\b|\berrno\b",
+    )
+    .unwrap()
+});
+
+/// The code in front of the region: the start line up to the region, or the previous line
+/// if the region starts a line. This is usually the condition guarding the region.
+fn get_guard(region: &CodeRegion) -> String {
+    let line = get_file_line(&region.file_path, region.start_line);
+    let prefix = line
+        .get(..(region.start_column as usize).saturating_sub(1))
+        .unwrap_or("");
+
+    if prefix.trim().is_empty() && region.start_line > 1 {
+        get_file_line(&region.file_path, region.start_line - 1)
+    } else {
+        prefix.to_string()
+    }
+}
+
+/// Checks if the guard tests a pointer for `NULL` that was assigned from an allocation shortly before.
+fn is_allocation_check(region: &CodeRegion, guard: &str) -> bool {
+    let Some(captures) = NULL_CHECK_RE.captures(guard) else {
+        return false;
+    };
+    let pointer = captures
+        .iter()
+        .skip(1)
+        .flatten()
+        .next()
+        .map(|m| m.as_str())
+        .unwrap_or("");
+
+    let first_line = region.start_line.saturating_sub(ALLOCATION_LOOKBACK).max(1);
+    (first_line..=region.start_line).any(|line| {
+        ALLOCATION_RE
+            .captures_iter(&get_file_line(&region.file_path, line))
+            .any(|captures| &captures[1] == pointer)
+    })
+}
+
+/// Checks if every statement of the code only prints or logs something.
+fn is_logging_only(code: &str) -> bool {
+    let statements: Vec<&str> = code
+        .split([';', '{', '}'])
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty() && *statement != "break")
+        .collect();
+
+    !statements.is_empty() && statements.iter().all(|s| LOGGING_RE.is_match(s))
+}
+
+/// Guesses the category of an uncovered region. The first matching category wins, in this order:
+/// assertion, allocation-failure, error-handling, logging, functional.
+pub fn classify_region(region: &CodeRegion) -> RegionCategory {
+    let code = strip_comments(&get_file_part(
+        &region.file_path,
+        region.start_line,
+        region.start_column,
+        region.end_line,
+        region.end_column,
+    ));
+
+    let in_assert_macro = region.expansions.iter().any(|site| {
+        site.macro_name
+            .as_ref()
+            .is_some_and(|name| name.to_lowercase().contains("assert"))
+    });
+    if in_assert_macro || ASSERTION_RE.is_match(&code) {
+        return RegionCategory::Assertion;
+    }
+
+    let guard = strip_comments(&get_guard(region));
+    if code.contains("ENOMEM")
+        || code.to_lowercase().contains("out of memory")
+        || is_allocation_check(region, &guard)
+    {
+        return RegionCategory::AllocationFailure;
+    }
+
+    if ERROR_RE.is_match(&code) {
+        return RegionCategory::ErrorHandling;
+    }
+
+    if is_logging_only(&code) {
+        return RegionCategory::Logging;
+    }
+
+    RegionCategory::Functional
+}
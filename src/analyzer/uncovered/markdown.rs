@@ -0,0 +1,289 @@
+use super::model::{CodeRegion, PartiallyCoveredFunction, PartiallyCoveredPredicate};
+use super::report::{describe_expansion, get_file_part};
+use super::uncovered_analyzer::Output;
+use crate::analyzer::line_coverage::format_line_ranges;
+use crate::analyzer::tokenizer::{has_executable_tokens, trim_snippet};
+use crate::changes::ChangedLines;
+use crate::config::MarkdownOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The parts of a partially covered function to report, i.e., those touched by the change.
+struct Findings<'a> {
+    function: &'a PartiallyCoveredFunction,
+    predicates: Vec<&'a PartiallyCoveredPredicate>,
+    regions: Vec<&'a CodeRegion>,
+    uncovered_lines: Vec<u64>,
+}
+
+impl Findings<'_> {
+    fn is_empty(&self) -> bool {
+        self.predicates.is_empty() && self.regions.is_empty() && self.uncovered_lines.is_empty()
+    }
+}
+
+fn percent(covered: u64, total: u64) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", covered as f64 * 100.0 / total as f64)
+    }
+}
+
+/// Formats source code as inline code for a list or table cell. Only its first line with
+/// executable code is kept, the rest is elided, and `|` is escaped.
+fn inline_code(code: &str) -> String {
+    let lines: Vec<&str> = code
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let shown = lines
+        .iter()
+        .find(|line| has_executable_tokens(line))
+        .or(lines.first())
+        .copied()
+        .unwrap_or_default();
+    let elided = if lines.len() > 1 { " …" } else { "" };
+    format!("`{}`{}", shown.replace('|', "\\|"), elided)
+}
+
+pub fn fence_language(file_path: &str) -> &'static str {
+    match Path::new(file_path).extension().and_then(|e| e.to_str()) {
+        Some("c" | "h") => "c",
+        Some("cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx") => "cpp",
+        Some("rs") => "rust",
+        _ => "",
+    }
+}
+
+fn findings<'a>(
+    function: &'a PartiallyCoveredFunction,
+    changes: Option<&ChangedLines>,
+) -> Findings<'a> {
+    let touched = |file_path: &str, start_line: u64, end_line: u64| {
+        changes.is_none_or(|changes| changes.contains(file_path, start_line, end_line))
+    };
+
+    Findings {
+        function,
+        predicates: function
+            .partially_covered_predicates
+            .iter()
+            .filter(|p| touched(&p.file_path, p.start_line, p.end_line))
+            .collect(),
+        regions: function
+            .uncovered_regions
+            .iter()
+            .filter(|r| touched(&r.file_path, r.start_line, r.end_line))
+            .collect(),
+        uncovered_lines: function
+            .uncovered_lines
+            .iter()
+            .copied()
+            .filter(|&line| touched(&function.file_path, line, line))
+            .collect(),
+    }
+}
+
+/// Writes a summary of the uncovered analysis in GitHub-flavored markdown, for comments on
+/// merge requests: a totals table and the partially covered functions with the most one-sided
+/// predicates and uncovered regions, each with its source in a collapsible block. If the
+/// changed lines are given, only the uncovered code they touch is reported.
+pub fn write_markdown(
+    output: &Output,
+    options: &MarkdownOptions,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let changes = options.changes.as_ref();
+
+    writeln!(writer, "## Coverage Summary\n")?;
+    writeln!(writer, "| | Covered | Total | % |")?;
+    writeln!(writer, "|---|---:|---:|---:|")?;
+    let coverage = &output.coverage;
+    for (name, covered, total) in [
+        (
+            "Branches",
+            coverage.covered_branches,
+            coverage.total_branches,
+        ),
+        (
+            "Functions",
+            coverage.covered_functions,
+            coverage.total_functions,
+        ),
+    ] {
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} |",
+            name,
+            covered,
+            total,
+            percent(covered, total)
+        )?;
+    }
+    writeln!(writer)?;
+
+    if let Some(changes) = changes {
+        let uncovered_lines: Vec<(&str, Vec<u64>)> = output
+            .uncovered_lines
+            .iter()
+            .map(|file| {
+                let lines = file
+                    .lines
+                    .iter()
+                    .copied()
+                    .filter(|&line| changes.contains(&file.file_path, line, line))
+                    .collect::<Vec<u64>>();
+                (file.file_path.as_str(), lines)
+            })
+            .filter(|(_, lines)| !lines.is_empty())
+            .collect();
+
+        if uncovered_lines.is_empty() {
+            writeln!(writer, "All instrumented changed lines were executed.\n")?;
+        } else {
+            writeln!(writer, "### Uncovered Changed Lines\n")?;
+            for (file_path, lines) in uncovered_lines {
+                writeln!(writer, "- `{}`: {}", file_path, format_line_ranges(&lines))?;
+            }
+            writeln!(writer)?;
+        }
+    }
+
+    let mut functions: Vec<Findings> = output
+        .uncovered_functions
+        .iter()
+        .map(|function| findings(function, changes))
+        .filter(|findings| !findings.is_empty())
+        .collect();
+    // the sort is stable, so functions with as many findings keep the order of the report
+    functions.sort_by_key(|findings| {
+        std::cmp::Reverse(findings.predicates.len() + findings.regions.len())
+    });
+
+    if functions.is_empty() {
+        let scope = if changes.is_some() {
+            " in the changed code"
+        } else {
+            ""
+        };
+        return writeln!(writer, "No partially covered functions{}.", scope);
+    }
+
+    writeln!(
+        writer,
+        "### Partially Covered Functions{} ({} of {})\n",
+        if changes.is_some() {
+            " in the Change"
+        } else {
+            ""
+        },
+        functions.len().min(options.top),
+        functions.len()
+    )?;
+    for findings in functions.iter().take(options.top) {
+        write_function(findings, writer)?;
+    }
+    Ok(())
+}
+
+fn write_function(findings: &Findings, writer: &mut dyn Write) -> io::Result<()> {
+    let function = findings.function;
+    let location = match &function.whole_function {
+        Some(whole_function) => format!("{}:{}", function.file_path, whole_function.start_line),
+        None => function.file_path.clone(),
+    };
+    writeln!(writer, "#### `{}` ({})\n", function.function_name, location)?;
+
+    if !findings.predicates.is_empty() {
+        writeln!(writer, "| One-sided predicate | Result | Location |")?;
+        writeln!(writer, "|---|---|---|")?;
+        for predicate in &findings.predicates {
+            let mut status = if predicate.true_count == 0 {
+                format!("always false ({}x)", predicate.false_count)
+            } else {
+                format!("always true ({}x)", predicate.true_count)
+            };
+            if let Some(expansion) = describe_expansion(&predicate.expansions) {
+                status = format!("{}, {}", status, expansion);
+            }
+            writeln!(
+                writer,
+                "| {} | {} | {}:{}:{} |",
+                inline_code(&get_file_part(
+                    &predicate.file_path,
+                    predicate.start_line,
+                    predicate.start_column,
+                    predicate.end_line,
+                    predicate.end_column
+                )),
+                status,
+                predicate.file_path,
+                predicate.start_line,
+                predicate.start_column
+            )?;
+        }
+        writeln!(writer)?;
+    }
+
+    let regions: Vec<(&CodeRegion, String)> = findings
+        .regions
+        .iter()
+        .map(|region| {
+            let snippet = get_file_part(
+                &region.file_path,
+                region.start_line,
+                region.start_column,
+                region.end_line,
+                region.end_column,
+            );
+            (*region, snippet)
+        })
+        .filter(|(_, snippet)| has_executable_tokens(snippet))
+        .collect();
+    if !regions.is_empty() {
+        writeln!(writer, "Uncovered regions:\n")?;
+        for (region, snippet) in regions {
+            let mut details = vec![format!(
+                "{}:{}-{}",
+                region.file_path, region.start_line, region.end_line
+            )];
+            details.extend(describe_expansion(&region.expansions));
+            details.extend(region.category.map(|category| category.name().to_string()));
+            writeln!(
+                writer,
+                "- {} ({})",
+                inline_code(trim_snippet(&snippet)),
+                details.join(", ")
+            )?;
+        }
+        writeln!(writer)?;
+    }
+
+    if !findings.uncovered_lines.is_empty() {
+        writeln!(
+            writer,
+            "Uncovered lines: {}\n",
+            format_line_ranges(&findings.uncovered_lines)
+        )?;
+    }
+
+    if let Some(whole_function) = &function.whole_function {
+        writeln!(writer, "<details><summary>Source</summary>\n")?;
+        writeln!(writer, "```{}", fence_language(&whole_function.file_path))?;
+        writeln!(
+            writer,
+            "{}",
+            get_file_part(
+                &whole_function.file_path,
+                whole_function.start_line,
+                whole_function.start_column,
+                whole_function.end_line,
+                whole_function.end_column
+            )
+        )?;
+        writeln!(writer, "```\n\n</details>\n")?;
+    }
+    Ok(())
+}
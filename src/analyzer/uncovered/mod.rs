@@ -1,5 +1,14 @@
+mod category;
+mod markdown;
+mod prompt;
 mod uncovered_analyzer;
 mod report;
 mod model;
 
-pub use uncovered_analyzer::UncoveredAnalyzer;
\ No newline at end of file
+pub use category::{RegionCategory, RegionCategoryFilter};
+pub use uncovered_analyzer::{Coverage, Output as UncoveredOutput, UncoveredAnalyzer};
+pub use model::{
+    get_partially_covered_predicates, get_uncovered, get_uncovered_regions, CodeRegion,
+    ExpansionSite, PartiallyCoveredFunction, PartiallyCoveredPredicate,
+};
+pub use report::{get_file_line, get_file_lines, get_file_part};
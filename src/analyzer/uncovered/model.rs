@@ -1,15 +1,31 @@
 use regex::Regex;
+use schemars::JsonSchema;
 use std::collections::HashMap;
+use std::sync::LazyLock;
 
 /// Analysis the uncovered parts in the program.
 /// In detail, it will find out:
 /// 1. Partially covered predicates in each function. It means the predicate always outputs the same value in current coverage.
 /// 2. Uncovered regions in each function.
-use llvm_cov_json::{Branch, CoverageReport, FunctionMetrics, RegionKind};
+use llvm_cov_json::{Branch, CoverageReport, FunctionMetrics, Region, RegionKind};
 
+use crate::analyzer::line_coverage::LineCoverageMap;
+use crate::analyzer::tokenizer::has_executable_tokens;
+use crate::analyzer::uncovered::category::{classify_region, RegionCategory, RegionCategoryFilter};
 use crate::analyzer::uncovered::report::get_file_part;
+use crate::merge::Instantiation;
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// The place where a macro (or an included file) was expanded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, JsonSchema)]
+pub struct ExpansionSite {
+    /// Name of the expanded macro, `None` if the use site does not start with an identifier.
+    pub macro_name: Option<String>,
+    pub file_path: String,
+    pub line: u64,
+    pub column: u64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, JsonSchema)]
 pub struct PartiallyCoveredPredicate {
     pub file_path: String,
     pub start_line: u64,
@@ -18,24 +34,48 @@ pub struct PartiallyCoveredPredicate {
     pub end_column: u64,
     pub true_count: u64,
     pub false_count: u64,
+    /// The expansions this predicate is located in, innermost first.
+    /// Empty if the predicate is not part of a macro expansion.
+    #[serde(default)]
+    pub expansions: Vec<ExpansionSite>,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, JsonSchema)]
 pub struct CodeRegion {
     pub file_path: String,
     pub start_line: u64,
     pub start_column: u64,
     pub end_line: u64,
     pub end_column: u64, // Exclusive
+    /// The expansions this region is located in, innermost first.
+    #[serde(default)]
+    pub expansions: Vec<ExpansionSite>,
+    /// Only set for uncovered regions.
+    pub category: Option<RegionCategory>,
+    /// The category was asked to be downweighted, so the region is listed after the others.
+    #[serde(default)]
+    pub downweighted: bool,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, JsonSchema)]
 pub struct PartiallyCoveredFunction {
     pub function_name: String,
     pub file_path: String,
     pub partially_covered_predicates: Vec<PartiallyCoveredPredicate>,
     pub uncovered_regions: Vec<CodeRegion>,
+    /// Instrumented lines of the function that were never executed.
+    #[serde(default)]
+    pub uncovered_lines: Vec<u64>,
     pub whole_function: Option<CodeRegion>,
+    /// The instantiations merged into this function, if it is a template or generic function.
+    #[serde(default)]
+    pub instantiations: Vec<Instantiation>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, JsonSchema)]
+pub struct UncoveredLines {
+    pub file_path: String,
+    pub lines: Vec<u64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -80,16 +120,17 @@ fn merge_regions_in_place(a: &mut CodeRegion, b: &CodeRegion) {
     a.start_column = start_pos.column;
     a.end_line = end_pos.line;
     a.end_column = end_pos.column;
-    // file_path remains the same
+    // file_path and expansions remain the same
 }
 
 fn merge_uncovered_regions(uncovered_regions: Vec<CodeRegion>) -> Vec<CodeRegion> {
-    let mut regions_by_file: HashMap<String, Vec<CodeRegion>> = HashMap::new();
+    let mut regions_by_file: HashMap<(String, Vec<ExpansionSite>), Vec<CodeRegion>> =
+        HashMap::new();
 
-    // Group regions by file path
+    // Group regions by file path and expansion, so that two uses of the same macro are kept apart
     for region in uncovered_regions {
         regions_by_file
-            .entry(region.file_path.clone())
+            .entry((region.file_path.clone(), region.expansions.clone()))
             .or_default()
             .push(region);
     }
@@ -128,10 +169,63 @@ fn merge_uncovered_regions(uncovered_regions: Vec<CodeRegion>) -> Vec<CodeRegion
     merged_regions_all_files
 }
 
+/// Maps every expanded file id of a function to the expansion region it was expanded from.
+fn get_expansion_regions<'a>(function: &'a FunctionMetrics) -> HashMap<u64, &'a Region> {
+    function
+        .regions
+        .iter()
+        .filter(|region| region.kind == RegionKind::Expansion)
+        .map(|region| (region.expanded_file_id, region))
+        .collect()
+}
+
+static MACRO_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*([A-Za-z_][A-Za-z0-9_]*)").unwrap());
+
+/// Resolves the chain of expansions a file id originates from, innermost first.
+/// The last entry is the use site in the function's own source code.
+fn get_expansion_sites(
+    function: &FunctionMetrics,
+    expansion_regions: &HashMap<u64, &Region>,
+    file_id: u64,
+) -> Vec<ExpansionSite> {
+    let mut sites = Vec::new();
+    let mut current = file_id;
+
+    // the number of file ids bounds the depth, which also protects against malformed cycles
+    while let Some(region) = expansion_regions.get(&current) {
+        if sites.len() >= function.filenames.len() {
+            break;
+        }
+
+        let file_path = function.filenames[region.file_id as usize];
+        let use_site = get_file_part(
+            file_path,
+            region.line_start,
+            region.column_start,
+            region.line_end,
+            region.column_end,
+        );
+        let macro_name = MACRO_NAME_RE
+            .captures(&use_site)
+            .map(|captures| captures[1].to_string());
+
+        sites.push(ExpansionSite {
+            macro_name,
+            file_path: file_path.to_string(),
+            line: region.line_start,
+            column: region.column_start,
+        });
+        current = region.file_id;
+    }
+
+    sites
+}
+
 /// Checks if a function is partially covered and returns uncovered branches if any.
 /// A function is partially covered if it is called at least once and has at least one branch that is partially covered.
 /// A branch is partially covered if it has either an uncovered true or false execution count.
-fn get_partially_covered_predicates(
+pub fn get_partially_covered_predicates(
     function: &FunctionMetrics,
 ) -> Option<Vec<PartiallyCoveredPredicate>> {
     // first we check if the function is called at least once
@@ -152,6 +246,7 @@ fn get_partially_covered_predicates(
     if uncovered_branches.is_empty() {
         None
     } else {
+        let expansion_regions = get_expansion_regions(function);
         Some(
             uncovered_branches
                 .iter()
@@ -163,15 +258,17 @@ fn get_partially_covered_predicates(
                     end_column: branch.column_end,
                     true_count: branch.execution_count,
                     false_count: branch.false_execution_count,
+                    expansions: get_expansion_sites(function, &expansion_regions, branch.file_id),
                 })
                 .collect(),
         )
     }
 }
 
-fn is_pure_comment(region: &CodeRegion) -> bool {
-    // get the file content with get_file_part
-    let mut snippet = get_file_part(
+/// Checks if the region contains executable code, i.e., more than comments,
+/// preprocessor lines, braces and `;`.
+fn has_executable_code(region: &CodeRegion) -> bool {
+    let snippet = get_file_part(
         &region.file_path,
         region.start_line,
         region.start_column,
@@ -179,19 +276,16 @@ fn is_pure_comment(region: &CodeRegion) -> bool {
         region.end_column,
     );
 
-    // Remove block comments
-    let block_comment_re = Regex::new(r"/\*[^*]*\*+(?:[^/*][^*]*\*+)*/").unwrap();
-    snippet = block_comment_re.replace_all(&snippet, "").to_string();
-
-    // Remove line comments
-    let line_comment_re = Regex::new(r"//[^\n]*(?:\n|$)").unwrap();
-    snippet = line_comment_re.replace_all(&snippet, "").to_string();
-
-    snippet.trim().is_empty()
+    has_executable_tokens(&snippet)
 }
 
-fn get_uncovered_regions(function: &FunctionMetrics) -> Vec<CodeRegion> {
+/// Collects the never executed code regions of a function, merged and without pure comments.
+pub fn get_uncovered_regions(
+    function: &FunctionMetrics,
+    category_filter: &RegionCategoryFilter,
+) -> Vec<CodeRegion> {
     // if a function has partially covered predicates, it must have uncovered regions, so we can always return something
+    let expansion_regions = get_expansion_regions(function);
     let uncovered_regions: Vec<CodeRegion> = function
         .regions
         .iter()
@@ -202,6 +296,9 @@ fn get_uncovered_regions(function: &FunctionMetrics) -> Vec<CodeRegion> {
             start_column: region.column_start,
             end_line: region.line_end,
             end_column: region.column_end,
+            expansions: get_expansion_sites(function, &expansion_regions, region.file_id),
+            category: None,
+            downweighted: false,
         })
         .collect();
 
@@ -212,16 +309,35 @@ fn get_uncovered_regions(function: &FunctionMetrics) -> Vec<CodeRegion> {
     // 3. repeat 1 and 2 until no more regions can be merged
     let merged_uncovered_regions = merge_uncovered_regions(uncovered_regions);
 
-    // run a simple region identification to remove the regions that are purely comment
-    // this is to avoid reporting the comment regions as uncovered regions
-    merged_uncovered_regions
+    // remove the regions without executable code, e.g. only comments or closing braces
+    // this is to avoid reporting them as uncovered regions
+    let mut regions: Vec<CodeRegion> = merged_uncovered_regions
         .into_iter()
-        .filter(|region| !is_pure_comment(region))
-        .collect()
+        .filter(has_executable_code)
+        .map(|mut region| {
+            let category = classify_region(&region);
+            region.category = Some(category);
+            region.downweighted = category_filter.downweighted.contains(&category);
+            region
+        })
+        .filter(|region| {
+            !region
+                .category
+                .is_some_and(|category| category_filter.excluded.contains(&category))
+        })
+        .collect();
+
+    // downweighted regions go last, the sort is stable so the others keep their order
+    regions.sort_by_key(|region| region.downweighted);
+    regions
 }
 
 /// Identify partially covered functions, and get the uncovered areas.
-pub fn get_uncovered(coverage_report: &CoverageReport) -> Vec<PartiallyCoveredFunction> {
+pub fn get_uncovered(
+    coverage_report: &CoverageReport,
+    line_coverage: &LineCoverageMap,
+    category_filter: &RegionCategoryFilter,
+) -> Vec<PartiallyCoveredFunction> {
     let mut uncovered_functions = Vec::new();
 
     for function in &coverage_report.data[0].functions {
@@ -233,21 +349,40 @@ pub fn get_uncovered(coverage_report: &CoverageReport) -> Vec<PartiallyCoveredFu
             }
 
             let first_code_region = first_code_region.unwrap();
+            let file_path = function.filenames[first_code_region.file_id as usize];
             uncovered_functions.push(PartiallyCoveredFunction {
                 function_name: function.name.to_string(),
-                file_path: function.filenames[first_code_region.file_id as usize].to_string(),
+                file_path: file_path.to_string(),
                 partially_covered_predicates: uncovered_branches,
-                uncovered_regions: get_uncovered_regions(function),
+                uncovered_regions: get_uncovered_regions(function, category_filter),
+                uncovered_lines: line_coverage.uncovered_lines(
+                    file_path,
+                    first_code_region.line_start,
+                    first_code_region.line_end,
+                ),
                 whole_function: Some(CodeRegion {
                     file_path: function.filenames[first_code_region.file_id as usize].to_string(),
                     start_line: first_code_region.line_start,
                     start_column: first_code_region.column_start,
                     end_line: first_code_region.line_end,
                     end_column: first_code_region.column_end,
+                    expansions: Vec::new(),
+                    category: None,
+                    downweighted: false,
                 }),
+                instantiations: Vec::new(),
             });
         }
     }
 
     uncovered_functions
 }
+
+/// Get the uncovered lines of every file in the report.
+pub fn get_uncovered_lines(line_coverage: &LineCoverageMap) -> Vec<UncoveredLines> {
+    line_coverage
+        .uncovered_lines_by_file()
+        .into_iter()
+        .map(|(file_path, lines)| UncoveredLines { file_path, lines })
+        .collect()
+}
@@ -0,0 +1,786 @@
+use super::markdown::fence_language;
+use super::model::{CodeRegion, PartiallyCoveredFunction, PartiallyCoveredPredicate};
+use super::uncovered_analyzer::Output;
+use crate::analyzer::tokenizer::{tokenize, Token, TokenKind};
+use crate::config::PromptOptions;
+use crate::export::{evaluations, stable_hash};
+use llvm_cov_json::CoverageReport;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const HEADER_EXTENSIONS: [&str; 4] = ["h", "hh", "hpp", "hxx"];
+
+/// A line and column, both starting at 1.
+type Position = (u64, u64);
+
+/// Where a function of the report is defined. Its first region starts at the opening brace of
+/// the body, so the signature is the code before it.
+struct FunctionSpan {
+    name: String,
+    file_path: String,
+    start: Position,
+    end: Position,
+}
+
+/// What the units need from the report besides the results of the analysis: the functions,
+/// to find callers, and the source files, to find definitions.
+pub struct PromptContext {
+    functions: Vec<FunctionSpan>,
+    files: BTreeSet<String>,
+}
+
+impl PromptContext {
+    pub fn new(coverage_report: &CoverageReport) -> Self {
+        let data = &coverage_report.data[0];
+        let mut locations = HashSet::new();
+        let functions = data
+            .functions
+            .iter()
+            .filter_map(|function| {
+                let region = function.regions.first()?;
+                let span = FunctionSpan {
+                    name: function.name.to_string(),
+                    file_path: function.filenames[region.file_id as usize].to_string(),
+                    start: (region.line_start, region.column_start),
+                    end: (region.line_end, region.column_end),
+                };
+                locations
+                    .insert((span.file_path.clone(), span.start))
+                    .then_some(span)
+            })
+            .collect();
+
+        Self {
+            functions,
+            files: data
+                .files
+                .iter()
+                .map(|file| file.filename.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// The content of the source files read so far, `None` if a file can't be read.
+#[derive(Default)]
+struct Sources {
+    files: HashMap<String, Option<String>>,
+}
+
+impl Sources {
+    fn content(&mut self, file_path: &str) -> Option<&str> {
+        self.files
+            .entry(file_path.to_string())
+            .or_insert_with(|| fs::read_to_string(file_path).ok())
+            .as_deref()
+    }
+
+    /// Returns the code from `start` to `end` (exclusive), empty if the file can't be read.
+    fn part(&mut self, file_path: &str, start: Position, end: Position) -> String {
+        let Some(content) = self.content(file_path) else {
+            return String::new();
+        };
+        let lines: Vec<&str> = content
+            .split('\n')
+            .skip((start.0 as usize).saturating_sub(1))
+            .take((end.0.saturating_sub(start.0) + 1) as usize)
+            .collect();
+
+        let last = lines.len().saturating_sub(1);
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let from = if i == 0 { start.1 as usize - 1 } else { 0 };
+                let to = if i == last {
+                    (end.1 as usize - 1).min(line.len())
+                } else {
+                    line.len()
+                };
+                line.get(from.min(to)..to).unwrap_or(line)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns the signature of the function on one line: the code before the opening brace,
+    /// following it back over the preceding lines until the end of the previous declaration.
+    fn signature(&mut self, function: &FunctionSpan) -> String {
+        let Some(content) = self.content(&function.file_path) else {
+            return function.name.clone();
+        };
+        let lines: Vec<&str> = content.split('\n').collect();
+        let index = (function.start.0 as usize).saturating_sub(1);
+        let Some(line) = lines.get(index) else {
+            return function.name.clone();
+        };
+
+        let mut parts = vec![line
+            .get(..(function.start.1 as usize).saturating_sub(1))
+            .unwrap_or(line)];
+        for previous in lines[..index].iter().rev().take(5) {
+            let trimmed = previous.trim();
+            if trimmed.is_empty()
+                || trimmed.starts_with('#')
+                || trimmed.starts_with("//")
+                || trimmed.ends_with(['{', '}', ';'])
+                || trimmed.ends_with("*/")
+            {
+                break;
+            }
+            parts.insert(0, previous);
+        }
+        parts
+            .join(" ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A macro, type or enumeration found in the sources.
+struct Definition {
+    file_path: String,
+    line: u64,
+    text: String,
+}
+
+/// The definitions of the source files of the report and of the headers next to them, by
+/// every name they define: macros, struct, union and enum tags, typedefs and enumerators.
+#[derive(Default)]
+struct Definitions {
+    definitions: Vec<Definition>,
+    by_name: HashMap<String, usize>,
+}
+
+impl Definitions {
+    fn new(files: &BTreeSet<String>, sources: &mut Sources) -> Self {
+        let directories: BTreeSet<PathBuf> = files
+            .iter()
+            .filter_map(|file| Some(Path::new(file).parent()?.to_path_buf()))
+            .collect();
+        let mut paths = files.clone();
+        for directory in directories {
+            let Ok(entries) = fs::read_dir(&directory) else {
+                continue;
+            };
+            paths.extend(
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.extension()
+                            .and_then(|extension| extension.to_str())
+                            .is_some_and(|extension| HEADER_EXTENSIONS.contains(&extension))
+                    })
+                    .map(|path| path.to_string_lossy().to_string()),
+            );
+        }
+
+        let mut definitions = Self::default();
+        for path in paths {
+            if let Some(content) = sources.content(&path) {
+                definitions.add_file(&path, content);
+            }
+        }
+        definitions
+    }
+
+    fn add_file(&mut self, file_path: &str, content: &str) {
+        let tokens = tokenize(content);
+        let line_of = |offset: usize| content[..offset].matches('\n').count() as u64 + 1;
+        let mut depth = 0usize;
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = tokens[i];
+            match (token.kind, token.text) {
+                (TokenKind::Preprocessor, text) => {
+                    if let Some(name) = defined_macro(text) {
+                        self.add(&[name], file_path, line_of(token.start), text);
+                    }
+                }
+                (TokenKind::Punctuator, "{") => depth += 1,
+                (TokenKind::Punctuator, "}") => depth = depth.saturating_sub(1),
+                (TokenKind::Identifier, "struct" | "union" | "enum" | "typedef") if depth == 0 => {
+                    if let Some(end) = declaration_end(&tokens[i..]) {
+                        let declaration = &tokens[i..=i + end];
+                        let names = declared_names(declaration);
+                        if !names.is_empty() {
+                            self.add(
+                                &names,
+                                file_path,
+                                line_of(token.start),
+                                &content[token.start..declaration[end].end],
+                            );
+                        }
+                        i += end + 1;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    fn add(&mut self, names: &[&str], file_path: &str, line: u64, text: &str) {
+        self.definitions.push(Definition {
+            file_path: file_path.to_string(),
+            line,
+            text: text.trim_end().to_string(),
+        });
+        for name in names {
+            // the first definition wins, e.g. of a macro defined differently per platform
+            self.by_name
+                .entry(name.to_string())
+                .or_insert(self.definitions.len() - 1);
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<(usize, &Definition)> {
+        let index = *self.by_name.get(name)?;
+        Some((index, &self.definitions[index]))
+    }
+}
+
+/// Returns the name of the macro defined by a preprocessor directive.
+fn defined_macro(directive: &str) -> Option<&str> {
+    let rest = directive.strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("define")?;
+    let rest = rest.trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    (end > 0).then(|| &rest[..end])
+}
+
+/// Returns the index of the `;` ending the declaration that starts with the first token, or
+/// `None` if it is not a declaration, e.g. a function returning a struct.
+fn declaration_end(tokens: &[Token]) -> Option<usize> {
+    let is_typedef = tokens[0].text == "typedef";
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Punctuator {
+            continue;
+        }
+        match token.text {
+            "{" => depth += 1,
+            "}" => depth = depth.checked_sub(1)?,
+            "(" if depth == 0 && !is_typedef => return None,
+            ";" if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the names a struct, union, enum or typedef declaration defines: the tag, the
+/// typedef name and the enumerators. Declarations of variables with a tag type define nothing.
+fn declared_names<'a>(declaration: &[Token<'a>]) -> Vec<&'a str> {
+    let code: Vec<_> = declaration
+        .iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let is_typedef = code[0].text == "typedef";
+    let tag_keyword = if is_typedef { 1 } else { 0 };
+    let has_body = code.iter().any(|token| token.text == "{");
+    let mut names = Vec::new();
+
+    if matches!(
+        code.get(tag_keyword).map(|token| token.text),
+        Some("struct" | "union" | "enum")
+    ) {
+        if let Some(tag) = code
+            .get(tag_keyword + 1)
+            .filter(|token| token.kind == TokenKind::Identifier)
+        {
+            if has_body || is_typedef {
+                names.push(tag.text);
+            }
+        }
+    }
+    if !has_body && !is_typedef {
+        return Vec::new();
+    }
+
+    if code[tag_keyword].text == "enum" {
+        let mut depth = 0;
+        for pair in code.windows(2) {
+            match pair[0].text {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            if depth == 1
+                && matches!(pair[0].text, "{" | ",")
+                && pair[1].kind == TokenKind::Identifier
+            {
+                names.push(pair[1].text);
+            }
+        }
+    }
+
+    if is_typedef {
+        // the name of a function pointer type follows `(*`, otherwise it comes last
+        let mut depth = 0;
+        let mut last = None;
+        let mut pointer = None;
+        for (i, token) in code.iter().enumerate() {
+            match token.text {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ if depth == 0 && token.kind == TokenKind::Identifier => last = Some(token.text),
+                "(" if depth == 0
+                    && pointer.is_none()
+                    && code.get(i + 1).is_some_and(|next| next.text == "*") =>
+                {
+                    pointer = code
+                        .get(i + 2)
+                        .filter(|name| name.kind == TokenKind::Identifier)
+                        .map(|name| name.text);
+                }
+                _ => {}
+            }
+        }
+        names.extend(pointer.or(last));
+    }
+    names
+}
+
+/// Returns the name a function is called by, without namespaces, template arguments,
+/// parameters or the file prefix of static functions.
+fn call_name(function_name: &str) -> &str {
+    let name = function_name.split('(').next().unwrap_or(function_name);
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit(':').next().unwrap_or(name).trim()
+}
+
+/// Returns the identifiers of the code in order of their first appearance.
+fn identifiers(code: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tokenize(code)
+        .into_iter()
+        .filter(|token| token.kind == TokenKind::Identifier)
+        .filter(|token| seen.insert(token.text))
+        .map(|token| token.text.to_string())
+        .collect()
+}
+
+/// Shows a path relative to the working directory if it is below it.
+fn display_path(file_path: &str, root: &Path) -> String {
+    Path::new(file_path).strip_prefix(root).map_or_else(
+        |_| file_path.to_string(),
+        |relative| relative.display().to_string(),
+    )
+}
+
+/// Checks if the code between a predicate and an uncovered region belongs to the same
+/// statement, i.e., the region is the code behind the predicate rather than later code. After
+/// the parenthesis around the predicate closed, the statement may only end to be followed by
+/// its `else`.
+fn is_behind_predicate(between: &str) -> bool {
+    let tokens: Vec<Token> = tokenize(between)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let mut parentheses = 0i64;
+    let mut braces = 0i64;
+    let mut closed = false;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.text {
+            "(" => parentheses += 1,
+            ")" => {
+                parentheses -= 1;
+                closed |= parentheses < 0;
+            }
+            "{" if closed => braces += 1,
+            "}" | ";" if closed => {
+                if token.text == "}" {
+                    braces -= 1;
+                }
+                if braces < 0 {
+                    return false;
+                }
+                if braces == 0 {
+                    let rest = &tokens[i + 1..];
+                    return rest.first().is_some_and(|next| next.text == "else")
+                        && rest[1..].iter().all(|token| token.text == "{");
+                }
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Finds the uncovered region the predicate guards: the first one after it that is still part
+/// of the same statement.
+fn uncovered_body<'a>(
+    function: &'a PartiallyCoveredFunction,
+    predicate: &PartiallyCoveredPredicate,
+    sources: &mut Sources,
+) -> Option<&'a CodeRegion> {
+    let end = (predicate.end_line, predicate.end_column);
+    let region = function
+        .uncovered_regions
+        .iter()
+        .filter(|region| {
+            region.file_path == predicate.file_path
+                && (region.start_line, region.start_column) >= end
+        })
+        .min_by_key(|region| (region.start_line, region.start_column))?;
+
+    let between = sources.part(
+        &predicate.file_path,
+        end,
+        (region.start_line, region.start_column),
+    );
+    is_behind_predicate(&between).then_some(region)
+}
+
+/// Keeps as many lines around `focus` (an index into the lines) as fit into `budget`
+/// characters, always including the first and the last line, and marks the elided ones.
+fn fit_lines(code: &str, focus: usize, budget: usize) -> Option<String> {
+    let lines: Vec<&str> = code.lines().collect();
+    if code.len() <= budget {
+        return Some(code.to_string());
+    }
+    let last = lines.len().checked_sub(1)?;
+    let length = |range: std::ops::RangeInclusive<usize>| -> usize {
+        lines[range].iter().map(|line| line.len() + 1).sum()
+    };
+
+    if last < 2 {
+        return None;
+    }
+    let focus = focus.clamp(1, last - 1);
+    let indent: String = lines[focus]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let marker = |elided: usize| format!("{}// ... {} lines elided", indent, elided);
+    // the first and the last line, and room for the markers of the elided lines, which can't
+    // be longer than the one for all lines
+    let fixed = lines[0].len() + lines[last].len() + 1 + 2 * (marker(last).len() + 1);
+    if fixed + lines[focus].len() + 1 > budget {
+        return None;
+    }
+    let (mut from, mut to) = (focus, focus);
+    loop {
+        let grown = (from.saturating_sub(1).max(1), (to + 1).min(last - 1));
+        if grown == (from, to) || fixed + length(grown.0..=grown.1) > budget {
+            break;
+        }
+        (from, to) = grown;
+    }
+
+    let mut kept = vec![lines[0].to_string()];
+    if from > 1 {
+        kept.push(marker(from - 1));
+    }
+    kept.extend(lines[from..=to].iter().map(|line| line.to_string()));
+    if to < last - 1 {
+        kept.push(marker(last - 1 - to));
+    }
+    kept.push(lines[last].to_string());
+    Some(kept.join("\n"))
+}
+
+/// The callers of each function, by the name they call it with.
+fn callers_by_name(context: &PromptContext, sources: &mut Sources) -> HashMap<String, Vec<usize>> {
+    let mut callers: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, function) in context.functions.iter().enumerate() {
+        let body = sources.part(&function.file_path, function.start, function.end);
+        let tokens: Vec<_> = tokenize(&body)
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Comment)
+            .collect();
+        let called: BTreeSet<&str> = tokens
+            .windows(2)
+            .filter(|pair| pair[0].kind == TokenKind::Identifier && pair[1].text == "(")
+            .map(|pair| pair[0].text)
+            .collect();
+        for name in called {
+            callers.entry(name.to_string()).or_default().push(index);
+        }
+    }
+    callers
+}
+
+/// The parts of one unit, before they are written.
+struct Unit<'a> {
+    function: &'a PartiallyCoveredFunction,
+    predicate: &'a PartiallyCoveredPredicate,
+    predicate_code: String,
+    body: Option<(&'a CodeRegion, String)>,
+    enclosing: Option<String>,
+    definitions: Vec<&'a Definition>,
+    callers: Vec<(String, &'a FunctionSpan)>,
+    left_out: usize,
+}
+
+/// Writes one self-contained unit of context per one-sided predicate, for an LLM to come up
+/// with inputs that take the other direction. Each unit has the predicate, the uncovered code
+/// behind it, the enclosing function, the definitions of the macros and types it uses and the
+/// signatures of the callers, as far as they fit into the budget, in that order of priority.
+/// The ID of a unit hashes the function, the file and the code of the predicate, so it stays
+/// the same across runs as long as the predicate does.
+pub fn write_prompt(
+    output: &Output,
+    context: &PromptContext,
+    options: &PromptOptions,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let root = std::env::current_dir().unwrap_or_default();
+    let mut sources = Sources::default();
+    let definitions = Definitions::new(&context.files, &mut sources);
+    let callers = callers_by_name(context, &mut sources);
+    let mut occurrences: HashMap<u64, usize> = HashMap::new();
+
+    for function in &output.uncovered_functions {
+        for predicate in &function.partially_covered_predicates {
+            let unit = build_unit(
+                function,
+                predicate,
+                context,
+                &definitions,
+                &callers,
+                options.budget,
+                &mut sources,
+            );
+
+            let hash = stable_hash(&[
+                &function.function_name,
+                &display_path(&predicate.file_path, &root),
+                &unit
+                    .predicate_code
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ]);
+            let occurrence = occurrences.entry(hash).or_default();
+            *occurrence += 1;
+            let id = match *occurrence {
+                1 => format!("{:016x}", hash),
+                n => format!("{:016x}-{}", hash, n),
+            };
+            write_unit(&id, &unit, &root, writer)?;
+        }
+    }
+    Ok(())
+}
+
+fn build_unit<'a>(
+    function: &'a PartiallyCoveredFunction,
+    predicate: &'a PartiallyCoveredPredicate,
+    context: &'a PromptContext,
+    definitions: &'a Definitions,
+    callers: &HashMap<String, Vec<usize>>,
+    budget: usize,
+    sources: &mut Sources,
+) -> Unit<'a> {
+    let predicate_code = sources.part(
+        &predicate.file_path,
+        (predicate.start_line, predicate.start_column),
+        (predicate.end_line, predicate.end_column),
+    );
+    let mut remaining = budget.saturating_sub(predicate_code.len());
+
+    let body = uncovered_body(function, predicate, sources).and_then(|region| {
+        let code = sources.part(
+            &region.file_path,
+            (region.start_line, region.start_column),
+            (region.end_line, region.end_column),
+        );
+        let code = fit_lines(&code, 0, remaining)?;
+        remaining = remaining.saturating_sub(code.len());
+        Some((region, code))
+    });
+
+    let span = function.whole_function.as_ref().and_then(|whole_function| {
+        context.functions.iter().find(|span| {
+            span.file_path == whole_function.file_path
+                && span.start == (whole_function.start_line, whole_function.start_column)
+        })
+    });
+    let enclosing = function.whole_function.as_ref().and_then(|whole_function| {
+        let signature = span.map(|span| sources.signature(span)).unwrap_or_default();
+        let code = format!(
+            "{} {}",
+            signature,
+            sources.part(
+                &whole_function.file_path,
+                (whole_function.start_line, whole_function.start_column),
+                (whole_function.end_line, whole_function.end_column),
+            )
+        );
+        let focus = predicate
+            .start_line
+            .saturating_sub(whole_function.start_line) as usize;
+        let code = fit_lines(code.trim_start(), focus, remaining)?;
+        remaining = remaining.saturating_sub(code.len());
+        Some(code)
+    });
+
+    // the macros the predicate is expanded from first, then the names used by the predicate,
+    // the body and the function, then the names used by the definitions found
+    let mut queue: VecDeque<String> = predicate
+        .expansions
+        .iter()
+        .filter_map(|site| site.macro_name.clone())
+        .collect();
+    queue.extend(identifiers(&predicate_code));
+    if let Some((_, code)) = &body {
+        queue.extend(identifiers(code));
+    }
+    queue.extend(identifiers(enclosing.as_deref().unwrap_or_default()));
+
+    let mut included = Vec::new();
+    let mut seen = HashSet::new();
+    let mut left_out = 0;
+    while let Some(name) = queue.pop_front() {
+        let Some((index, definition)) = definitions.get(&name) else {
+            continue;
+        };
+        if !seen.insert(index) {
+            continue;
+        }
+        if definition.text.len() > remaining {
+            left_out += 1;
+            continue;
+        }
+        remaining -= definition.text.len();
+        queue.extend(identifiers(&definition.text));
+        included.push(definition);
+    }
+    included.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+
+    let mut unit_callers = Vec::new();
+    for &index in callers
+        .get(call_name(&function.function_name))
+        .into_iter()
+        .flatten()
+    {
+        let caller = &context.functions[index];
+        if span.is_some_and(|span| std::ptr::eq(span, caller)) {
+            continue;
+        }
+        let signature = sources.signature(caller);
+        if signature.len() > remaining {
+            left_out += 1;
+            continue;
+        }
+        remaining -= signature.len();
+        unit_callers.push((signature, caller));
+    }
+
+    Unit {
+        function,
+        predicate,
+        predicate_code,
+        body,
+        enclosing,
+        definitions: included,
+        callers: unit_callers,
+        left_out,
+    }
+}
+
+fn write_unit(id: &str, unit: &Unit, root: &Path, writer: &mut dyn Write) -> io::Result<()> {
+    let predicate = unit.predicate;
+    let file_path = display_path(&predicate.file_path, root);
+    let language = fence_language(&predicate.file_path);
+    let (result, count, goal) = if predicate.false_count == 0 {
+        ("true", predicate.true_count, "false")
+    } else {
+        ("false", predicate.false_count, "true")
+    };
+
+    writeln!(writer, "<<<UNIT {}>>>", id)?;
+    writeln!(writer, "Function: `{}`", unit.function.function_name)?;
+    writeln!(
+        writer,
+        "Predicate: `{}` at {}:{}:{} was always {} ({})",
+        unit.predicate_code
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+        file_path,
+        predicate.start_line,
+        predicate.start_column,
+        result,
+        evaluations(count)
+    )?;
+    for site in &predicate.expansions {
+        writeln!(
+            writer,
+            "Expanded from {} at {}:{}:{}",
+            site.macro_name.as_ref().map_or_else(
+                || "an include".to_string(),
+                |name| format!("macro `{}`", name)
+            ),
+            display_path(&site.file_path, root),
+            site.line,
+            site.column
+        )?;
+    }
+    writeln!(writer, "Goal: an input that makes the predicate {}.", goal)?;
+
+    if let Some((region, code)) = &unit.body {
+        writeln!(
+            writer,
+            "\n### Uncovered Code Behind the Predicate ({}:{}-{})\n",
+            display_path(&region.file_path, root),
+            region.start_line,
+            region.end_line
+        )?;
+        writeln!(writer, "```{}\n{}\n```", language, code)?;
+    }
+    if let (Some(code), Some(whole_function)) = (&unit.enclosing, &unit.function.whole_function) {
+        writeln!(
+            writer,
+            "\n### Enclosing Function ({}:{})\n",
+            display_path(&whole_function.file_path, root),
+            whole_function.start_line
+        )?;
+        writeln!(writer, "```{}\n{}\n```", language, code)?;
+    }
+    if !unit.definitions.is_empty() {
+        writeln!(writer, "\n### Definitions\n")?;
+        writeln!(writer, "```{}", language)?;
+        for definition in &unit.definitions {
+            writeln!(
+                writer,
+                "// {}:{}\n{}",
+                display_path(&definition.file_path, root),
+                definition.line,
+                definition.text
+            )?;
+        }
+        writeln!(writer, "```")?;
+    }
+    if !unit.callers.is_empty() {
+        writeln!(writer, "\n### Callers\n")?;
+        for (signature, caller) in &unit.callers {
+            writeln!(
+                writer,
+                "- `{}` ({}:{})",
+                signature,
+                display_path(&caller.file_path, root),
+                caller.start.0
+            )?;
+        }
+    }
+    if unit.left_out > 0 {
+        writeln!(
+            writer,
+            "\n({} more definitions and callers left out to fit the budget)",
+            unit.left_out
+        )?;
+    }
+    writeln!(writer, "<<<UNIT_END>>>\n")
+}
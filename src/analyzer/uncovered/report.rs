@@ -1,7 +1,34 @@
-use super::model::PartiallyCoveredFunction;
+use super::model::{ExpansionSite, PartiallyCoveredFunction, UncoveredLines};
+use crate::analyzer::line_coverage::format_line_ranges;
+use crate::analyzer::tokenizer::{has_executable_tokens, trim_snippet};
+use log::warn;
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, Write};
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// The source files read so far, `None` for the ones that could not be read.
+static SOURCES: LazyLock<Mutex<HashMap<String, Option<Arc<str>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Read a source file, at most once per run. A file that can't be read, e.g. because
+/// `--path-map` or `--source-root` doesn't resolve it, is reported once and gives `None`.
+pub fn read_source(file_path: &str) -> Option<Arc<str>> {
+    let mut sources = SOURCES.lock().unwrap_or_else(|e| e.into_inner());
+    sources
+        .entry(file_path.to_string())
+        .or_insert_with(|| match fs::read_to_string(file_path) {
+            Ok(content) => Some(content.into()),
+            Err(e) => {
+                warn!("Can't read source file {}: {}", file_path, e);
+                None
+            }
+        })
+        .clone()
+}
 
 /// Read the file and return the part of the file specified by the start and end lines and columns.
+/// Returns a placeholder if the file can't be read.
 pub fn get_file_part(
     file_path: &str,
     start_line: u64,
@@ -9,26 +36,32 @@ pub fn get_file_part(
     end_line: u64,
     end_column: u64, // exclusive
 ) -> String {
-    let file_content = fs::read_to_string(file_path).expect("Failed to read file");
+    let Some(file_content) = read_source(file_path) else {
+        return format!("<source unavailable: {}>", file_path);
+    };
     let lines = file_content.split('\n').collect::<Vec<&str>>();
 
-    // Handle empty file case
-    if lines.is_empty() {
-        return "[!] Empty file".to_string();
-    }
-
     // Clamp line indices to valid range
     let start_index = (start_line as usize).saturating_sub(1).min(lines.len() - 1);
-    let end_index = (end_line as usize).saturating_sub(1).min(lines.len() - 1);
-    let mut has_error =
-        start_index != start_line as usize - 1 || end_index != end_line as usize - 1;
+    let mut end_index = (end_line as usize).saturating_sub(1).min(lines.len() - 1);
+    let mut has_error = start_line == 0
+        || start_index != start_line as usize - 1
+        || end_index != (end_line as usize).saturating_sub(1);
+    if end_index < start_index {
+        end_index = start_index;
+        has_error = true;
+    }
 
     let mut result = String::new();
 
     for (i, line) in lines[start_index..=end_index].iter().enumerate() {
-        let mut start = if i == 0 { start_column as usize - 1 } else { 0 };
+        let mut start = if i == 0 {
+            (start_column as usize).saturating_sub(1)
+        } else {
+            0
+        };
         let mut end = if i == end_index - start_index {
-            end_column as usize - 1
+            (end_column as usize).saturating_sub(1)
         } else {
             line.len()
         };
@@ -50,6 +83,13 @@ pub fn get_file_part(
             end = start;
             has_error = true;
         }
+        // Columns count bytes, so they may point into a multi-byte character
+        while !line.is_char_boundary(start) {
+            start -= 1;
+        }
+        while !line.is_char_boundary(end) {
+            end += 1;
+        }
 
         if i > 0 {
             result.push('\n');
@@ -67,22 +107,74 @@ pub fn get_file_part(
     }
 }
 
+/// Read the whole file, split into lines without the line breaks.
+/// Returns no lines if the file can't be read.
+pub fn get_file_lines(file_path: &str) -> Vec<String> {
+    read_source(file_path)
+        .map(|content| content.split('\n').map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Read a single line of the file, without the line break. Returns an empty string if the line
+/// or the file doesn't exist.
+pub fn get_file_line(file_path: &str, line: u64) -> String {
+    read_source(file_path)
+        .and_then(|content| {
+            content
+                .split('\n')
+                .nth((line as usize).checked_sub(1)?)
+                .map(String::from)
+        })
+        .unwrap_or_default()
+}
+
+/// Describe where a macro expansion was used, based on the outermost expansion site.
+pub fn describe_expansion(expansions: &[ExpansionSite]) -> Option<String> {
+    let outermost = expansions.last()?;
+    let location = format!(
+        "{}:{}:{}",
+        outermost.file_path, outermost.line, outermost.column
+    );
+
+    Some(match &outermost.macro_name {
+        Some(name) => format!("inside macro {} used at {}", name, location),
+        None => format!("expanded at {}", location),
+    })
+}
+
 /// Pretty print the uncovered functions.
-pub fn print_uncovered(uncovered_functions: &Vec<PartiallyCoveredFunction>) {
+pub fn write_uncovered(
+    uncovered_functions: &Vec<PartiallyCoveredFunction>,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
     if uncovered_functions.is_empty() {
-        println!("No partially covered functions found.");
-        return;
+        writeln!(writer, "No partially covered functions found.")?;
+        return Ok(());
     }
 
-    println!("\nPartially Covered Functions Report");
-    println!("=================================\n");
+    writeln!(writer, "\nPartially Covered Functions Report")?;
+    writeln!(writer, "=================================\n")?;
 
     for function in uncovered_functions {
-        println!("Function: {}", function.function_name);
-        println!("Location: {}", function.file_path);
+        writeln!(writer, "Function: {}", function.function_name)?;
+        writeln!(writer, "Location: {}", function.file_path)?;
+        if !function.instantiations.is_empty() {
+            writeln!(writer, "Instantiations:")?;
+            for instantiation in &function.instantiations {
+                writeln!(
+                    writer,
+                    "  {} (executed {} times, {}/{} branches)",
+                    instantiation.name,
+                    instantiation.count,
+                    instantiation.covered_branches,
+                    instantiation.total_branches
+                )?;
+            }
+        }
 
         if let Some(whole_function) = &function.whole_function {
-            println!(
+            writeln!(
+                writer,
                 "{}",
                 get_file_part(
                     &whole_function.file_path,
@@ -91,12 +183,12 @@ pub fn print_uncovered(uncovered_functions: &Vec<PartiallyCoveredFunction>) {
                     whole_function.end_line,
                     whole_function.end_column
                 )
-            );
+            )?;
         }
 
         if !function.partially_covered_predicates.is_empty() {
-            println!("\nPartially Covered Predicates:");
-            println!("----------------------------");
+            writeln!(writer, "\nPartially Covered Predicates:")?;
+            writeln!(writer, "----------------------------")?;
             for (i, pred) in function.partially_covered_predicates.iter().enumerate() {
                 let status = if pred.true_count == 0 && pred.false_count == 0 {
                     "never executed"
@@ -106,25 +198,32 @@ pub fn print_uncovered(uncovered_functions: &Vec<PartiallyCoveredFunction>) {
                     "always true"
                 };
 
-                println!(
+                let status = match describe_expansion(&pred.expansions) {
+                    Some(expansion) => format!("{}, {}", status, expansion),
+                    None => status.to_string(),
+                };
+
+                writeln!(
+                    writer,
                     "  {}. {} ({})",
                     i + 1,
                     get_file_part(
-                        &function.file_path, // it's nearly impossible that the predicate is in a different file
+                        &pred.file_path, // inside a macro, this is the file defining the macro
                         pred.start_line,
                         pred.start_column,
                         pred.end_line,
                         pred.end_column
                     ),
                     status
-                );
+                )?;
             }
         }
 
         if !function.uncovered_regions.is_empty() {
-            println!("\nUncovered Regions:");
-            println!("-----------------");
+            writeln!(writer, "\nUncovered Regions:")?;
+            writeln!(writer, "-----------------")?;
             let mut display_index = 1;
+            let mut printed_downweighted_header = false;
             for region in function.uncovered_regions.iter() {
                 let file_part = get_file_part(
                     &region.file_path,
@@ -134,26 +233,71 @@ pub fn print_uncovered(uncovered_functions: &Vec<PartiallyCoveredFunction>) {
                     region.end_column,
                 );
 
-                // Skip if file part only contains whitespace
-                if file_part.trim().is_empty() {
+                // Skip if file part only contains comments, braces and whitespace
+                if !has_executable_tokens(&file_part) {
                     continue;
                 }
+                let file_part = trim_snippet(&file_part);
 
-                println!(
-                    "  {}. {} ({}:{}:{} - {}:{})",
-                    display_index,
-                    file_part,
+                if region.downweighted && !printed_downweighted_header {
+                    writeln!(writer, "  Downweighted:")?;
+                    printed_downweighted_header = true;
+                }
+
+                let mut details = vec![format!(
+                    "{}:{}:{} - {}:{}",
                     region.file_path,
                     region.start_line,
                     region.start_column,
                     region.end_line,
                     region.end_column
-                );
+                )];
+                details.extend(describe_expansion(&region.expansions));
+                details.extend(region.category.map(|category| category.name().to_string()));
+                writeln!(
+                    writer,
+                    "  {}. {} ({})",
+                    display_index,
+                    file_part,
+                    details.join(", ")
+                )?;
 
                 display_index += 1;
             }
         }
 
-        println!("\n---------------------------------\n");
+        if !function.uncovered_lines.is_empty() {
+            writeln!(
+                writer,
+                "\nUncovered Lines: {}",
+                format_line_ranges(&function.uncovered_lines)
+            )?;
+        }
+
+        writeln!(writer, "\n---------------------------------\n")?;
+    }
+    Ok(())
+}
+
+/// Pretty print the uncovered lines of each file.
+pub fn write_uncovered_lines(
+    uncovered_lines: &[UncoveredLines],
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    if uncovered_lines.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "\nUncovered Lines by File")?;
+    writeln!(writer, "=======================\n")?;
+
+    for file in uncovered_lines {
+        writeln!(
+            writer,
+            "{}: {}",
+            file.file_path,
+            format_line_ranges(&file.lines)
+        )?;
     }
+    Ok(())
 }
@@ -1,57 +1,222 @@
+use super::category::RegionCategoryFilter;
+use super::markdown::write_markdown;
 use super::model::get_uncovered;
+use super::model::get_uncovered_lines;
 use super::model::PartiallyCoveredFunction;
-use super::report::print_uncovered;
+use super::model::UncoveredLines;
+use super::prompt::{write_prompt, PromptContext};
+use super::report::write_uncovered;
+use super::report::write_uncovered_lines;
+use crate::analyzer::biased_branches::{get_biased_branches, write_biased_branches, BiasedBranch};
+use crate::analyzer::binaries::{write_binary_coverage, BinaryCoverage};
+use crate::analyzer::line_coverage::LineCoverageMap;
+use crate::config::{MarkdownOptions, PromptOptions};
 use crate::error::GetCovError;
+use crate::export::write_sarif;
+use crate::merge::{Instantiation, SourceLocation};
+use crate::schema::{RunMetadata, SCHEMA_VERSION};
 use crate::Analyzer;
 use llvm_cov_json::CoverageReport;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-#[derive(Debug, Serialize, Deserialize)]
+use std::collections::HashMap;
+use std::io::Write;
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Coverage {
-    covered_branches: u64,
-    total_branches: u64,
-    covered_functions: u64,
-    total_functions: u64,
+    pub covered_branches: u64,
+    pub total_branches: u64,
+    pub covered_functions: u64,
+    pub total_functions: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// File path, start line and column, end line and column.
+type BranchPosition<'a> = (&'a str, u64, u64, u64, u64);
+
+impl Coverage {
+    /// Computes the coverage of the functions left in the report, so that it reflects the filters.
+    /// Like llvm-cov, every branch counts twice (true and false), and branches at the same
+    /// position, e.g. of an inline function in a header, are merged.
+    pub fn from_report(coverage_report: &CoverageReport) -> Self {
+        let functions = &coverage_report.data[0].functions;
+        let mut branches: HashMap<BranchPosition, (u64, u64)> = HashMap::new();
+
+        for function in functions {
+            for branch in &function.branches {
+                let counts = branches
+                    .entry((
+                        function.filenames[branch.file_id as usize],
+                        branch.line_start,
+                        branch.column_start,
+                        branch.line_end,
+                        branch.column_end,
+                    ))
+                    .or_default();
+                counts.0 += branch.execution_count;
+                counts.1 += branch.false_execution_count;
+            }
+        }
+
+        Coverage {
+            covered_branches: branches
+                .values()
+                .map(|&(true_count, false_count)| {
+                    (true_count > 0) as u64 + (false_count > 0) as u64
+                })
+                .sum(),
+            total_branches: branches.len() as u64 * 2,
+            covered_functions: functions.iter().filter(|f| f.count > 0).count() as u64,
+            total_functions: functions.len() as u64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Output {
-    coverage: Coverage,
-    uncovered_functions: Vec<PartiallyCoveredFunction>,
+    /// The version of the output format, increased on incompatible changes. 0 for outputs
+    /// written before the format was versioned.
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Absent in outputs written before the format was versioned.
+    #[serde(default)]
+    pub metadata: Option<RunMetadata>,
+    pub coverage: Coverage,
+    pub uncovered_functions: Vec<PartiallyCoveredFunction>,
+    /// The functions that were never called. Absent in outputs written before it was added,
+    /// in which a function that isn't listed may be uncalled or fully covered.
+    #[serde(default)]
+    pub uncalled_functions: Option<Vec<String>>,
+    #[serde(default)]
+    pub uncovered_lines: Vec<UncoveredLines>,
+    /// Only present if a bias threshold is given.
+    pub biased_branches: Option<Vec<BiasedBranch>>,
+    /// Only present if the reports of several binaries are merged.
+    pub binaries: Option<Vec<BinaryCoverage>>,
 }
 
 pub struct UncoveredAnalyzer {
+    bias_threshold: Option<f64>,
+    category_filter: RegionCategoryFilter,
+    binaries: Option<Vec<BinaryCoverage>>,
+    instantiations: HashMap<SourceLocation, Vec<Instantiation>>,
+    metadata: Option<RunMetadata>,
     results: Option<Output>,
+    /// The functions and sources of the report, for the prompt output.
+    prompt_context: Option<PromptContext>,
 }
 
 impl UncoveredAnalyzer {
-    pub fn new() -> Self {
-        Self { results: None }
+    /// Creates the analyzer. If `bias_threshold` is given, the branches whose
+    /// true/false ratio exceeds it are reported as well. `category_filter` selects the
+    /// categories of uncovered regions to leave out or to list last. `binaries` is what each
+    /// binary covered, if the report merges several of them, `instantiations` are the
+    /// instantiations of the merged template and generic functions, and `metadata` describes
+    /// the run for the JSON output.
+    pub fn new(
+        bias_threshold: Option<f64>,
+        category_filter: RegionCategoryFilter,
+        binaries: Option<Vec<BinaryCoverage>>,
+        instantiations: HashMap<SourceLocation, Vec<Instantiation>>,
+        metadata: RunMetadata,
+    ) -> Self {
+        Self {
+            bias_threshold,
+            category_filter,
+            binaries,
+            instantiations,
+            metadata: Some(metadata),
+            results: None,
+            prompt_context: None,
+        }
     }
 }
 
 impl Analyzer for UncoveredAnalyzer {
     fn analyze(&mut self, coverage_report: &CoverageReport) -> Result<(), GetCovError> {
-        let program_report = &coverage_report.data[0];
-        let uncovered_functions = get_uncovered(coverage_report);
+        let line_coverage = LineCoverageMap::new(coverage_report);
+        let mut uncovered_functions =
+            get_uncovered(coverage_report, &line_coverage, &self.category_filter);
+        for function in &mut uncovered_functions {
+            let Some(whole_function) = &function.whole_function else {
+                continue;
+            };
+            let location = (
+                whole_function.file_path.clone(),
+                whole_function.start_line,
+                whole_function.start_column,
+            );
+            if let Some(instantiations) = self.instantiations.get(&location) {
+                function.instantiations = instantiations.clone();
+            }
+        }
 
         self.results = Some(Output {
-            coverage: Coverage {
-                covered_branches: program_report.summary.branches.covered,
-                total_branches: program_report.summary.branches.count,
-                covered_functions: program_report.summary.functions.covered,
-                total_functions: program_report.summary.functions.count,
-            },
+            schema_version: SCHEMA_VERSION,
+            metadata: self.metadata.take(),
+            coverage: Coverage::from_report(coverage_report),
             uncovered_functions,
+            uncalled_functions: Some(
+                coverage_report.data[0]
+                    .functions
+                    .iter()
+                    .filter(|function| function.count == 0)
+                    .map(|function| function.name.to_string())
+                    .collect(),
+            ),
+            uncovered_lines: get_uncovered_lines(&line_coverage),
+            biased_branches: self
+                .bias_threshold
+                .map(|threshold| get_biased_branches(coverage_report, threshold)),
+            binaries: self.binaries.take(),
         });
+        self.prompt_context = Some(PromptContext::new(coverage_report));
+        Ok(())
+    }
+
+    fn output_json(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        serde_json::to_writer_pretty(writer, self.results.as_ref().unwrap())?;
+        Ok(())
+    }
+
+    fn output_text(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        let results = self.results.as_ref().unwrap();
+        write_uncovered(&results.uncovered_functions, writer)?;
+        write_uncovered_lines(&results.uncovered_lines, writer)?;
+        if let (Some(biased_branches), Some(threshold)) =
+            (&results.biased_branches, self.bias_threshold)
+        {
+            write_biased_branches(biased_branches, threshold, writer)?;
+        }
+        if let Some(binaries) = &results.binaries {
+            write_binary_coverage(binaries, writer)?;
+        }
         Ok(())
     }
 
-    fn output_json(&self) -> Result<(), GetCovError> {
-        serde_json::to_writer_pretty(std::io::stdout(), self.results.as_ref().unwrap())?;
+    fn output_sarif(&self, writer: &mut dyn Write) -> Result<(), GetCovError> {
+        write_sarif(self.results.as_ref().unwrap(), writer)?;
         Ok(())
     }
 
-    fn output_text(&self) {
-        print_uncovered(&self.results.as_ref().unwrap().uncovered_functions);
+    fn output_markdown(
+        &self,
+        options: &MarkdownOptions,
+        writer: &mut dyn Write,
+    ) -> Result<(), GetCovError> {
+        write_markdown(self.results.as_ref().unwrap(), options, writer)?;
+        Ok(())
+    }
+
+    fn output_prompt(
+        &self,
+        options: &PromptOptions,
+        writer: &mut dyn Write,
+    ) -> Result<(), GetCovError> {
+        write_prompt(
+            self.results.as_ref().unwrap(),
+            self.prompt_context.as_ref().unwrap(),
+            options,
+            writer,
+        )?;
+        Ok(())
     }
 }
@@ -0,0 +1,139 @@
+use crate::error::GetCovError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// The lines touched by a change, to restrict a report to the code it affects. They are read
+/// either from a unified diff, e.g. the output of `git diff`, or from a list with one
+/// `PATH[:LINES]` entry per line, where `LINES` are line numbers and ranges like `3,10-20`.
+/// An entry without lines marks the whole file as changed.
+#[derive(Debug, Default)]
+pub struct ChangedLines {
+    /// The ranges of changed lines of each file, `None` if the whole file changed.
+    files: BTreeMap<String, Option<Vec<RangeInclusive<u64>>>>,
+}
+
+impl ChangedLines {
+    pub fn from_file(path: &Path) -> Result<Self, GetCovError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, GetCovError> {
+        if content
+            .lines()
+            .any(|line| line.starts_with("@@ ") || line.starts_with("+++ "))
+        {
+            Ok(Self::parse_diff(content))
+        } else {
+            Self::parse_list(content)
+        }
+    }
+
+    /// Collects the added lines of every file of a unified diff, by their number in the new file.
+    fn parse_diff(diff: &str) -> Self {
+        let mut changes = Self::default();
+        let mut file: Option<String> = None;
+        let mut line = 0;
+        let mut previous = "";
+
+        for text in diff.lines() {
+            // `+++` only starts a file after `---`, otherwise it is an added line
+            if let Some(path) = text
+                .strip_prefix("+++ ")
+                .filter(|_| previous.starts_with("--- "))
+            {
+                let path = path.split('\t').next().unwrap_or(path);
+                file = (path != "/dev/null")
+                    .then(|| path.strip_prefix("b/").unwrap_or(path).to_string());
+            } else if let Some(hunk) = text.strip_prefix("@@ ") {
+                // `@@ -start,count +start,count @@`
+                line = hunk
+                    .split_whitespace()
+                    .find_map(|range| range.strip_prefix('+'))
+                    .and_then(|range| range.split(',').next()?.parse().ok())
+                    .unwrap_or(0);
+            } else if let Some(file) = &file {
+                match text.as_bytes().first() {
+                    Some(b'+') => {
+                        changes.add_lines(file, line..=line);
+                        line += 1;
+                    }
+                    Some(b' ') | None => line += 1,
+                    // removed lines and `\ No newline at end of file`
+                    _ => {}
+                }
+            }
+            previous = text;
+        }
+        changes
+    }
+
+    fn parse_list(list: &str) -> Result<Self, GetCovError> {
+        let mut changes = Self::default();
+
+        for entry in list
+            .lines()
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let Some((path, lines)) = entry.rsplit_once(':').filter(|(_, lines)| {
+                lines
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || matches!(c, ',' | '-' | ' '))
+            }) else {
+                changes.files.insert(entry.to_string(), None);
+                continue;
+            };
+
+            for range in lines.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+                let invalid = || {
+                    GetCovError::ArgParse(format!("Invalid line range '{}' in {}", range, entry))
+                };
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                let start: u64 = start.trim().parse().map_err(|_| invalid())?;
+                let end: u64 = end.trim().parse().map_err(|_| invalid())?;
+                if start > end {
+                    return Err(invalid());
+                }
+                changes.add_lines(path, start..=end);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Adds a range of changed lines, extending the last range of the file if it ends right
+    /// before, as the consecutive added lines of a diff do.
+    fn add_lines(&mut self, path: &str, lines: RangeInclusive<u64>) {
+        let Some(ranges) = self
+            .files
+            .entry(path.to_string())
+            .or_insert(Some(Vec::new()))
+        else {
+            return;
+        };
+        match ranges.last_mut() {
+            Some(last) if last.end().saturating_add(1) == *lines.start() => {
+                *last = *last.start()..=*lines.end();
+            }
+            _ => ranges.push(lines),
+        }
+    }
+
+    /// Checks if any line between `start_line` and `end_line` (both inclusive) of the file
+    /// changed. Relative paths of the change match the end of the path of the file.
+    pub fn contains(&self, file_path: &str, start_line: u64, end_line: u64) -> bool {
+        self.files.iter().any(|(path, lines)| {
+            let same_file = file_path == path
+                || file_path
+                    .strip_suffix(path.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('/'));
+            same_file
+                && lines.as_ref().is_none_or(|ranges| {
+                    ranges
+                        .iter()
+                        .any(|range| *range.start() <= end_line && start_line <= *range.end())
+                })
+        })
+    }
+}
@@ -1,9 +1,18 @@
 use clap::ArgMatches;
 
-use crate::config::{AnalysisOptions, Config, OutputFormat, RunningMode, RunningOptions};
+use crate::analyzer::{RegionCategory, RegionCategoryFilter};
+use crate::changes::ChangedLines;
+use crate::config::{
+    AnalysisMode, AnalysisOptions, Config, MarkdownOptions, MergedBinary, OutputFormat,
+    PromptOptions, RunningMode, RunningOptions, Subcommand,
+};
 use crate::error::GetCovError;
+use crate::filter::ReportFilter;
+use crate::path_map::PathMapper;
 use clap::{Arg, Command};
+use regex::Regex;
 use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
 
 fn create_cli() -> Command {
@@ -11,14 +20,11 @@ fn create_cli() -> Command {
         .version("1.0")
         .author("Wenxuan Shi")
         .about("Coverage analysis tool")
-        .arg(
-            Arg::new("input")
-                .short('i')
-                .long("input")
-                .value_name("DIRECTORY")
-                .help("Sets the seed directory")
-                .required(false),
-        )
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .args(running_args())
+        .args(filter_args())
+        .args(path_args())
         .arg(
             Arg::new("all")
                 .long("all")
@@ -26,6 +32,77 @@ fn create_cli() -> Command {
                 .action(clap::ArgAction::SetTrue)
                 .required(false),
         )
+        .arg(
+            Arg::new("lines")
+                .long("lines")
+                .help("Report per-line execution counts")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("all")
+                .required(false),
+        )
+        .arg(
+            Arg::new("mcdc")
+                .long("mcdc")
+                .help("Report MC/DC condition coverage (requires -fcoverage-mcdc)")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["all", "lines"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("loops")
+                .long("loops")
+                .help("Report loops that never iterated more than once or were never skipped")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["all", "lines", "mcdc"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("switches")
+                .long("switches")
+                .help("Report switch statements with case labels that were never taken")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["all", "lines", "mcdc", "loops"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("function")
+                .long("function")
+                .value_name("NAME|REGEX")
+                .help("Report the matching functions in detail")
+                .conflicts_with_all(["all", "lines", "mcdc", "loops", "switches"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("bias-threshold")
+                .long("bias-threshold")
+                .value_name("RATIO")
+                .help("Also report branches taken at least RATIO times more often in one direction")
+                .value_parser(clap::value_parser!(f64))
+                .conflicts_with_all(["all", "lines", "mcdc", "loops", "switches", "function"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("exclude-category")
+                .long("exclude-category")
+                .value_name("CATEGORY")
+                .help("Leave uncovered regions of these categories out of the report")
+                .value_parser(RegionCategory::NAMES)
+                .value_delimiter(',')
+                .action(clap::ArgAction::Append)
+                .conflicts_with_all(["all", "lines", "mcdc", "loops", "switches", "function"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("downweight-category")
+                .long("downweight-category")
+                .value_name("CATEGORY")
+                .help("List uncovered regions of these categories after the others")
+                .value_parser(RegionCategory::NAMES)
+                .value_delimiter(',')
+                .action(clap::ArgAction::Append)
+                .conflicts_with_all(["all", "lines", "mcdc", "loops", "switches", "function"])
+                .required(false),
+        )
         .arg(
             Arg::new("text")
                 .long("text")
@@ -41,20 +118,299 @@ fn create_cli() -> Command {
                 .required(false),
         )
         .arg(
-            Arg::new("profdata")
-                .long("profdata")
-                .help("Use the provided profdata file")
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Output format; lcov and cobertura export the line and branch coverage")
+                .value_parser([
+                    "json",
+                    "text",
+                    "hybrid",
+                    "lcov",
+                    "cobertura",
+                    "sarif",
+                    "markdown",
+                    "prompt",
+                ])
+                .conflicts_with_all(["text", "hybrid"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("html")
+                .long("html")
+                .value_name("DIRECTORY")
+                .help("Write an HTML report with annotated source into DIRECTORY")
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all(["format", "text", "hybrid"])
+                .required(false),
+        )
+        .args(output_args())
+        .mut_arg("output", |arg| arg.conflicts_with("html"))
+        .arg(
+            Arg::new("manifest")
+                .long("manifest")
+                .value_name("FILE")
+                .help("Write a JSON manifest with the profdata files and the written outputs to FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(false),
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .value_name("N")
+                .help("Number of partially covered functions in the markdown summary")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("10"),
+        )
+        .arg(
+            Arg::new("changed")
+                .long("changed")
                 .value_name("FILE")
-                .required(false)
-                .action(clap::ArgAction::Set),
+                .help("Only summarize the uncovered code on the lines changed by a diff or a list of PATH[:LINES] in FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(false),
         )
         .arg(
-            Arg::new("executable")
-                .help("The command to run")
-                .required(true)
-                .num_args(1..)
-                .last(true),
+            Arg::new("budget")
+                .long("budget")
+                .value_name("CHARS")
+                .help("Characters of source code in each unit of the prompt format")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("6000"),
+        )
+        .subcommand(
+            Command::new("dict")
+                .about("Write the operands of one-sided predicates as an AFL/libFuzzer dictionary")
+                .args(running_args())
+                .args(filter_args())
+                .args(path_args())
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the dictionary to FILE instead of stdout")
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compare the coverage of two profdata files or saved GetCov outputs")
+                .args(filter_args())
+                .args(path_args())
+                .arg(
+                    Arg::new("base")
+                        .long("base")
+                        .value_name("FILE")
+                        .help("The profdata file or GetCov JSON output to compare against")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("head")
+                        .long("head")
+                        .value_name("FILE")
+                        .help("The profdata file or GetCov JSON output to compare")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("text")
+                        .long("text")
+                        .help("Output in text format")
+                        .action(clap::ArgAction::SetTrue)
+                        .required(false),
+                )
+                .args(output_args())
+                .arg(
+                    Arg::new("executable")
+                        .help("The instrumented binary, required when comparing profdata files")
+                        .required(false)
+                        .num_args(1..)
+                        .last(true),
+                ),
         )
+        .subcommand(
+            Command::new("show")
+                .about("Print a file or function with its line and branch counts")
+                // the target comes first, as the executable takes the last positional arguments
+                .arg(
+                    Arg::new("target")
+                        .value_name("FILE|FUNCTION")
+                        .help("A source file, matching the end of its path, or a function name or regex")
+                        .required(true),
+                )
+                .args(running_args())
+                .args(filter_args())
+                .args(path_args())
+                .arg(
+                    Arg::new("color")
+                        .long("color")
+                        .value_name("WHEN")
+                        .help("Color the output (default: only if stdout is a terminal)")
+                        .value_parser(["auto", "always", "never"])
+                        .default_value("auto"),
+                ),
+        )
+        .subcommand(
+            Command::new("tui")
+                .about("Browse the uncovered files, functions and predicates in the terminal")
+                .args(running_args())
+                .args(filter_args())
+                .args(path_args())
+                .arg(
+                    Arg::new("exclude-category")
+                        .long("exclude-category")
+                        .value_name("CATEGORY")
+                        .help("Leave uncovered regions of these categories out of the browser")
+                        .value_parser(RegionCategory::NAMES)
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append)
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Print the JSON Schema of the JSON output")
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Print the schema of the --all output instead of the default analysis")
+                        .action(clap::ArgAction::SetTrue)
+                        .required(false),
+                ),
+        )
+}
+
+/// The arguments that select the program and its inputs, shared by all subcommands.
+fn running_args() -> [Arg; 4] {
+    [
+        Arg::new("input")
+            .short('i')
+            .long("input")
+            .value_name("DIRECTORY")
+            .help("Sets the seed directory")
+            .required(false),
+        Arg::new("profdata")
+            .long("profdata")
+            .help("Use the provided profdata file")
+            .value_name("FILE")
+            .required(false)
+            .action(clap::ArgAction::Set),
+        Arg::new("merge")
+            .long("merge")
+            .value_name("BINARY=PROFDATA|DIRECTORY")
+            .help("Merge the coverage of another binary built from the same sources (repeatable)")
+            .value_parser(parse_merged_binary)
+            .action(clap::ArgAction::Append)
+            .required(false),
+        Arg::new("executable")
+            .help("The command to run")
+            .required(true)
+            .num_args(1..)
+            .last(true),
+    ]
+}
+
+/// The arguments that select the files and functions to analyze, shared by all subcommands.
+fn filter_args() -> [Arg; 5] {
+    let regex_arg = |id: &'static str, help: &'static str| {
+        Arg::new(id)
+            .long(id)
+            .value_name("REGEX")
+            .help(help)
+            .value_parser(parse_regex)
+            .action(clap::ArgAction::Append)
+            .required(false)
+    };
+
+    [
+        regex_arg(
+            "include-file",
+            "Only analyze files whose path matches REGEX",
+        ),
+        regex_arg("exclude-file", "Skip files whose path matches REGEX"),
+        regex_arg(
+            "include-function",
+            "Only analyze functions whose name matches REGEX",
+        ),
+        regex_arg(
+            "exclude-function",
+            "Skip functions whose name matches REGEX",
+        ),
+        Arg::new("ignore-filename-regex")
+            .long("ignore-filename-regex")
+            .value_name("REGEX")
+            .help("Passed to llvm-cov, which skips the matching source files")
+            .action(clap::ArgAction::Append)
+            .required(false),
+    ]
+}
+
+/// The arguments that map the source paths recorded at compile time, shared by all subcommands.
+fn path_args() -> [Arg; 2] {
+    [
+        Arg::new("path-map")
+            .long("path-map")
+            .value_name("FROM=TO")
+            .help("Replace the source path prefix FROM with TO (repeatable)")
+            .value_parser(parse_path_map)
+            .action(clap::ArgAction::Append)
+            .required(false),
+        Arg::new("source-root")
+            .long("source-root")
+            .value_name("DIRECTORY")
+            .help("Resolve relative source paths against DIRECTORY")
+            .required(false),
+    ]
+}
+
+/// The arguments that select the files to write the output to, shared with `diff`.
+fn output_args() -> [Arg; 2] {
+    [
+        Arg::new("output")
+            .short('o')
+            .long("output")
+            .value_name("FILE")
+            .help("Write the output to FILE instead of stdout")
+            .value_parser(clap::value_parser!(PathBuf))
+            .required(false),
+        Arg::new("emit")
+            .long("emit")
+            .value_name("FORMAT=FILE")
+            .help("Also write the output in FORMAT to FILE (repeatable)")
+            .value_parser(parse_emit)
+            .action(clap::ArgAction::Append)
+            .required(false),
+    ]
+}
+
+fn parse_emit(value: &str) -> Result<(OutputFormat, PathBuf), String> {
+    match value.split_once('=') {
+        Some((format, path)) if !path.is_empty() => match OutputFormat::from_name(format) {
+            Some(format) => Ok((format, PathBuf::from(path))),
+            None => Err(format!("unknown output format '{}'", format)),
+        },
+        _ => Err("expected FORMAT=FILE".to_string()),
+    }
+}
+
+fn parse_path_map(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((from, to)) if !from.is_empty() => Ok((from.to_string(), to.to_string())),
+        _ => Err("expected FROM=TO".to_string()),
+    }
+}
+
+fn parse_merged_binary(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((binary, source)) if !binary.is_empty() && !source.is_empty() => {
+            Ok((binary.to_string(), source.to_string()))
+        }
+        _ => Err("expected BINARY=PROFDATA or BINARY=DIRECTORY".to_string()),
+    }
+}
+
+fn parse_regex(value: &str) -> Result<Regex, String> {
+    Regex::new(value).map_err(|e| e.to_string())
 }
 
 /// Parses the command-line arguments and constructs a `Config` instance.
@@ -64,22 +420,67 @@ fn create_cli() -> Command {
 /// A `Result` containing `Config` or a `GetCovError`.
 pub fn parse_arguments() -> Result<Config, GetCovError> {
     let matches = create_cli().get_matches();
-    let (binary, args) = parse_executable(&matches)?;
 
-    let binary = if Path::new(&binary).exists() {
-        fs::canonicalize(&binary)
-            .map_err(GetCovError::Io)?
-            .to_str()
-            .ok_or_else(|| GetCovError::ArgParse("Path contains invalid Unicode".into()))?
-            .to_string()
+    // the program, its inputs and the filters are given to the subcommand, if any
+    let (subcommand, running_matches) = match matches.subcommand() {
+        Some(("dict", sub_matches)) => (
+            Subcommand::Dict {
+                output: sub_matches.get_one::<String>("output").map(PathBuf::from),
+            },
+            sub_matches,
+        ),
+        Some(("diff", sub_matches)) => (
+            Subcommand::Diff {
+                base: PathBuf::from(sub_matches.get_one::<String>("base").unwrap()),
+                head: PathBuf::from(sub_matches.get_one::<String>("head").unwrap()),
+            },
+            sub_matches,
+        ),
+        Some(("show", sub_matches)) => (
+            Subcommand::Show {
+                target: sub_matches.get_one::<String>("target").unwrap().clone(),
+                color: match sub_matches.get_one::<String>("color").unwrap().as_str() {
+                    "always" => true,
+                    "never" => false,
+                    _ => io::stdout().is_terminal(),
+                },
+            },
+            sub_matches,
+        ),
+        // checked before the program runs, which may take a while
+        Some(("tui", _)) if !io::stdout().is_terminal() => {
+            return Err(GetCovError::ArgParse(
+                "getcov tui needs a terminal, use getcov show to print the source".into(),
+            ))
+        }
+        Some(("tui", sub_matches)) => (Subcommand::Tui, sub_matches),
+        // the schema takes none of the other arguments, which are left unset
+        Some(("schema", sub_matches)) => (
+            Subcommand::Schema {
+                functions: sub_matches.get_flag("all"),
+            },
+            &matches,
+        ),
+        _ => (Subcommand::Analyze, &matches),
+    };
+    let is_diff = matches!(subcommand, Subcommand::Diff { .. });
+    let is_schema = matches!(subcommand, Subcommand::Schema { .. });
+
+    // diff only needs the binary to read profdata files, and takes the files instead of inputs
+    let (binary, args) = if is_schema || is_diff && !running_matches.contains_id("executable") {
+        (String::new(), Vec::new())
     } else {
-        return Err(GetCovError::ArgParse(format!(
-            "Binary '{}' not found",
-            binary
-        )));
+        let (binary, args) = parse_executable(running_matches)?;
+        (resolve_binary(&binary)?, args)
     };
 
-    let profdata_file = matches.get_one::<String>("profdata").map(PathBuf::from);
+    let profdata_file = if is_diff {
+        None
+    } else {
+        running_matches
+            .get_one::<String>("profdata")
+            .map(PathBuf::from)
+    };
 
     let running_mode = if profdata_file.is_some() {
         RunningMode::Profdata
@@ -87,30 +488,184 @@ pub fn parse_arguments() -> Result<Config, GetCovError> {
         RunningMode::Normal
     };
 
-    let running_options = if let Some(input_dir) = matches.get_one::<String>("input") {
+    let input_dir = if is_diff {
+        None
+    } else {
+        running_matches.get_one::<String>("input")
+    };
+    let mut running_options = if let Some(input_dir) = input_dir {
         create_options_from_input_dir(input_dir, &binary, &args)?
     } else {
         RunningOptions {
             binary,
             args_list: vec![args],
+            ignore_filename_regex: Vec::new(),
+            path_mapper: PathMapper::default(),
         }
     };
+    running_options.ignore_filename_regex = running_matches
+        .get_many::<String>("ignore-filename-regex")
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default();
+    running_options.path_mapper = PathMapper {
+        rules: running_matches
+            .get_many::<(String, String)>("path-map")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default(),
+        source_root: running_matches
+            .get_one::<String>("source-root")
+            .map(PathBuf::from),
+    };
+
+    let merged_binaries = if is_diff {
+        Vec::new()
+    } else {
+        running_matches
+            .get_many::<(String, String)>("merge")
+            .into_iter()
+            .flatten()
+            .map(|(binary, source)| parse_merged_binary_options(binary, source, &running_options))
+            .collect::<Result<_, _>>()?
+    };
+
+    let regexes = |id: &str| -> Vec<Regex> {
+        running_matches
+            .get_many::<Regex>(id)
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default()
+    };
+    let report_filter = ReportFilter {
+        include_files: regexes("include-file"),
+        exclude_files: regexes("exclude-file"),
+        include_functions: regexes("include-function"),
+        exclude_functions: regexes("exclude-function"),
+    };
+
+    let bias_threshold = matches.get_one::<f64>("bias-threshold").copied();
+    if bias_threshold.is_some_and(|ratio| ratio.is_nan() || ratio < 1.0) {
+        return Err(GetCovError::ArgParse(
+            "Bias threshold must be at least 1".into(),
+        ));
+    }
 
+    // the browser takes its own excluded categories
+    let category_matches = if matches!(subcommand, Subcommand::Tui) {
+        running_matches
+    } else {
+        &matches
+    };
+    let category_filter = RegionCategoryFilter {
+        excluded: parse_categories(category_matches, "exclude-category"),
+        downweighted: parse_categories(&matches, "downweight-category"),
+    };
+
+    // diff takes its own output files
+    let output_matches = if is_diff { running_matches } else { &matches };
     let analysis_options = AnalysisOptions {
-        extract_all_functions: matches.get_flag("all"),
-        output_format: if matches.get_flag("text") {
-            OutputFormat::Text
-        } else if matches.get_flag("hybrid") {
-            OutputFormat::Hybrid
+        mode: if matches.get_flag("all") {
+            AnalysisMode::ExtractFunctions
+        } else if matches.get_flag("lines") {
+            AnalysisMode::LineCoverage
+        } else if matches.get_flag("mcdc") {
+            AnalysisMode::Mcdc
+        } else if matches.get_flag("loops") {
+            AnalysisMode::Loops
+        } else if matches.get_flag("switches") {
+            AnalysisMode::Switches
+        } else if let Some(pattern) = matches.get_one::<String>("function") {
+            AnalysisMode::Function(pattern.clone())
         } else {
-            OutputFormat::Json
+            AnalysisMode::Uncovered
+        },
+        bias_threshold,
+        category_filter,
+        output_format: match matches
+            .get_one::<String>("format")
+            .and_then(|name| OutputFormat::from_name(name))
+        {
+            Some(format) => format,
+            None if matches.get_flag("text") || is_diff && running_matches.get_flag("text") => {
+                OutputFormat::Text
+            }
+            None if matches.get_flag("hybrid") => OutputFormat::Hybrid,
+            None => OutputFormat::Json,
+        },
+        output_file: output_matches.get_one::<PathBuf>("output").cloned(),
+        emits: output_matches
+            .get_many::<(OutputFormat, PathBuf)>("emit")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default(),
+        manifest: matches.get_one::<PathBuf>("manifest").cloned(),
+        html_dir: matches.get_one::<PathBuf>("html").cloned(),
+        markdown: MarkdownOptions {
+            top: *matches.get_one::<usize>("top").unwrap(),
+            changes: matches
+                .get_one::<PathBuf>("changed")
+                .map(|path| ChangedLines::from_file(path))
+                .transpose()?,
+        },
+        prompt: PromptOptions {
+            budget: *matches.get_one::<usize>("budget").unwrap(),
         },
     };
 
     Ok(Config {
+        subcommand,
         running_mode,
         running_options,
         analysis_options,
+        report_filter,
+        profdata_file,
+        merged_binaries,
+    })
+}
+
+/// Checks that the binary exists and returns its canonical path.
+fn resolve_binary(binary: &str) -> Result<String, GetCovError> {
+    if Path::new(binary).exists() {
+        Ok(fs::canonicalize(binary)
+            .map_err(GetCovError::Io)?
+            .to_str()
+            .ok_or_else(|| GetCovError::ArgParse("Path contains invalid Unicode".into()))?
+            .to_string())
+    } else {
+        Err(GetCovError::ArgParse(format!(
+            "Binary '{}' not found",
+            binary
+        )))
+    }
+}
+
+/// Creates the options of a merged binary, which runs the inputs in `source` if it is a
+/// directory and reads `source` as its profdata file otherwise. The binary gets the input
+/// file as its only argument and shares the report options of the main binary.
+fn parse_merged_binary_options(
+    binary: &str,
+    source: &str,
+    main_options: &RunningOptions,
+) -> Result<MergedBinary, GetCovError> {
+    let binary = resolve_binary(binary)?;
+    let (running_mode, mut running_options, profdata_file) = if Path::new(source).is_dir() {
+        (
+            RunningMode::Normal,
+            create_options_from_input_dir(source, &binary, &[])?,
+            None,
+        )
+    } else {
+        let options = RunningOptions {
+            binary,
+            args_list: vec![Vec::new()],
+            ignore_filename_regex: Vec::new(),
+            path_mapper: PathMapper::default(),
+        };
+        (RunningMode::Profdata, options, Some(PathBuf::from(source)))
+    };
+    running_options.ignore_filename_regex = main_options.ignore_filename_regex.clone();
+    running_options.path_mapper = main_options.path_mapper.clone();
+
+    Ok(MergedBinary {
+        running_mode,
+        running_options,
         profdata_file,
     })
 }
@@ -155,6 +710,8 @@ fn create_options_from_input_dir(
         Ok(RunningOptions {
             binary: binary.to_string(),
             args_list,
+            ignore_filename_regex: Vec::new(),
+            path_mapper: PathMapper::default(),
         })
     }
 }
@@ -209,3 +766,15 @@ fn parse_executable(matches: &ArgMatches) -> Result<(String, Vec<String>), GetCo
         None => Err(GetCovError::ArgParse("No executable provided".into())),
     }
 }
+
+/// Parses a list of region categories; the names are already validated by Clap.
+fn parse_categories(matches: &ArgMatches, id: &str) -> Vec<RegionCategory> {
+    matches
+        .get_many::<String>(id)
+        .map(|names| {
+            names
+                .filter_map(|name| RegionCategory::from_name(name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -103,7 +103,7 @@ fn collect_profraw_files(dir: &Path, prefix: &str) -> Result<Vec<PathBuf>, GetCo
         .filter(|path| {
             path.file_name()
                 .and_then(|os_str| os_str.to_str())
-                .map_or(false, |name| name.starts_with(prefix))
+                .is_some_and(|name| name.starts_with(prefix))
         })
         .collect())
 }
@@ -134,6 +134,12 @@ fn generate_coverage_report_json(
     let output = Command::new("llvm-cov")
         .arg("export")
         .arg(format!("--instr-profile={}", profdata_file.display()))
+        .args(
+            options
+                .ignore_filename_regex
+                .iter()
+                .map(|regex| format!("-ignore-filename-regex={}", regex)),
+        )
         .arg(&options.binary)
         .output()?;
 
@@ -1,23 +1,120 @@
 use std::path::PathBuf;
 
+use crate::analyzer::RegionCategoryFilter;
+use crate::changes::ChangedLines;
+use crate::filter::ReportFilter;
+use crate::path_map::PathMapper;
+
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    /// Run the selected analysis and print its results.
+    Analyze,
+    /// Write a fuzzer dictionary extracted from one-sided predicates.
+    Dict { output: Option<PathBuf> },
+    /// Compare the coverage of two profdata files or saved GetCov outputs.
+    Diff { base: PathBuf, head: PathBuf },
+    /// Print the JSON Schema of the uncovered analysis output, or of `--all` if `functions`.
+    Schema { functions: bool },
+    /// Print the source of the files or functions matching `target` with their line and
+    /// branch counts, colored if `color` is set.
+    Show { target: String, color: bool },
+    /// Browse the files, functions and uncovered code of the report in the terminal.
+    Tui,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum RunningMode {
     Normal,
     Profdata,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum OutputFormat {
     Json,
     Text,
     Hybrid,
+    /// An LCOV tracefile of the whole report, independent of the analysis mode.
+    Lcov,
+    /// A Cobertura XML document of the whole report, independent of the analysis mode.
+    Cobertura,
+    /// A SARIF log of the findings of the uncovered analysis.
+    Sarif,
+    /// A markdown summary of the uncovered analysis for code review.
+    Markdown,
+    /// One self-contained context unit per one-sided predicate, to be given to an LLM.
+    Prompt,
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Text => "text",
+            OutputFormat::Hybrid => "hybrid",
+            OutputFormat::Lcov => "lcov",
+            OutputFormat::Cobertura => "cobertura",
+            OutputFormat::Sarif => "sarif",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Prompt => "prompt",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(OutputFormat::Json),
+            "text" => Some(OutputFormat::Text),
+            "hybrid" => Some(OutputFormat::Hybrid),
+            "lcov" => Some(OutputFormat::Lcov),
+            "cobertura" => Some(OutputFormat::Cobertura),
+            "sarif" => Some(OutputFormat::Sarif),
+            "markdown" => Some(OutputFormat::Markdown),
+            "prompt" => Some(OutputFormat::Prompt),
+            _ => None,
+        }
+    }
+
+    /// Checks if the output is written by the analyzer, rather than exported from the report.
+    pub fn needs_analysis(&self) -> bool {
+        !matches!(self, OutputFormat::Lcov | OutputFormat::Cobertura)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AnalysisMode {
+    /// Report partially covered functions and their uncovered parts.
+    Uncovered,
+    /// List all functions in the program.
+    ExtractFunctions,
+    /// Report per-line execution counts.
+    LineCoverage,
+    /// Report MC/DC conditions that were not shown to independently affect their decision.
+    Mcdc,
+    /// Report loops that never iterated more than once or were never skipped.
+    Loops,
+    /// Report switch statements with case labels that were never taken.
+    Switches,
+    /// Report the functions matching a name or regex in detail.
+    Function(String),
 }
 
 #[derive(Debug)]
 pub struct Config {
+    pub subcommand: Subcommand,
     pub running_mode: RunningMode,
     pub running_options: RunningOptions,
     pub analysis_options: AnalysisOptions,
+    /// Selects the files and functions to analyze.
+    pub report_filter: ReportFilter,
+    pub profdata_file: Option<PathBuf>,
+    /// Further binaries built from the same sources, merged into one report.
+    pub merged_binaries: Vec<MergedBinary>,
+}
+
+/// A binary whose coverage is merged with that of the main one.
+#[derive(Debug)]
+pub struct MergedBinary {
+    pub running_mode: RunningMode,
+    pub running_options: RunningOptions,
     pub profdata_file: Option<PathBuf>,
 }
 
@@ -25,10 +122,42 @@ pub struct Config {
 pub struct RunningOptions {
     pub binary: String,
     pub args_list: Vec<Vec<String>>,
+    /// Passed to `llvm-cov export` as `-ignore-filename-regex`.
+    pub ignore_filename_regex: Vec<String>,
+    /// Maps the source paths in the report to paths on this machine.
+    pub path_mapper: PathMapper,
 }
 
 #[derive(Debug)]
 pub struct AnalysisOptions {
-    pub extract_all_functions: bool,
+    pub mode: AnalysisMode,
+    /// Report branches whose true/false ratio is at least this value.
+    pub bias_threshold: Option<f64>,
+    /// Categories of uncovered regions to leave out or to list last.
+    pub category_filter: RegionCategoryFilter,
     pub output_format: OutputFormat,
+    /// Where to write the output in `output_format`, stdout if not given.
+    pub output_file: Option<PathBuf>,
+    /// Further outputs of the same run, each written to its own file.
+    pub emits: Vec<(OutputFormat, PathBuf)>,
+    /// Where to write the manifest listing the profdata files and the written outputs.
+    pub manifest: Option<PathBuf>,
+    /// Write an HTML report into this directory instead of printing the results.
+    pub html_dir: Option<PathBuf>,
+    pub markdown: MarkdownOptions,
+    pub prompt: PromptOptions,
+}
+
+#[derive(Debug)]
+pub struct MarkdownOptions {
+    /// How many partially covered functions to list.
+    pub top: usize,
+    /// Only report the uncovered code on these lines.
+    pub changes: Option<ChangedLines>,
+}
+
+#[derive(Debug)]
+pub struct PromptOptions {
+    /// How many characters of source code each unit may contain at most.
+    pub budget: usize,
 }
@@ -0,0 +1,80 @@
+use cpp_demangle::{DemangleOptions, Symbol};
+use llvm_cov_json::CoverageReport;
+use std::borrow::Cow;
+
+/// Demangles Itanium C++ and Rust (legacy and v0) symbol names. Other names, e.g., of C
+/// functions, are returned as they are. Functions with internal linkage keep the `file:`
+/// prefix that llvm-cov gives them. Rust hashes and C++ return types are left out.
+pub fn demangle(name: &str) -> Cow<'_, str> {
+    let (prefix, symbol) = match name.rsplit_once(':') {
+        Some((file, symbol)) if symbol.starts_with("_Z") || symbol.starts_with("_R") => {
+            (&name[..file.len() + 1], symbol)
+        }
+        _ => ("", name),
+    };
+
+    // legacy Rust symbols are valid Itanium symbols as well, so Rust has to come first
+    let demangled = if let Ok(demangled) = rustc_demangle::try_demangle(symbol) {
+        format!("{:#}", demangled)
+    } else if symbol.starts_with("_Z") {
+        match Symbol::new(symbol).ok().and_then(|symbol| {
+            symbol
+                .demangle(&DemangleOptions::new().no_return_type())
+                .ok()
+        }) {
+            Some(demangled) => demangled,
+            None => return Cow::Borrowed(name),
+        }
+    } else {
+        return Cow::Borrowed(name);
+    };
+
+    Cow::Owned(format!("{}{}", prefix, demangled))
+}
+
+/// Replaces the names of all functions in the report by their demangled form.
+pub fn demangle_report(coverage_report: &mut CoverageReport) {
+    for export in &mut coverage_report.data {
+        for function in &mut export.functions {
+            if let Cow::Owned(demangled) = demangle(&function.name) {
+                function.name = Cow::Owned(demangled);
+            }
+        }
+    }
+}
+
+/// Returns the name of the source function of a demangled name, i.e., without its
+/// parameters and template or generic arguments, which is shared by all its instantiations.
+/// E.g., `ns::Box<int>::get(int) const` becomes `ns::Box::get`, `foo::<u8>` becomes `foo`.
+pub fn source_function_name(demangled: &str) -> String {
+    let mut name = String::with_capacity(demangled.len());
+    let mut angle_depth = 0;
+
+    for (i, c) in demangled.char_indices() {
+        match c {
+            '<' if angle_depth > 0 => angle_depth += 1,
+            // `operator<` and `operator<<` are names, `<T as Trait>::` starts a path
+            '<' if !name.ends_with("operator")
+                && !name.ends_with("operator<")
+                && name.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == ':') =>
+            {
+                if name.ends_with("::") {
+                    name.truncate(name.len() - 2);
+                }
+                angle_depth = 1;
+            }
+            '>' if angle_depth > 0 => angle_depth -= 1,
+            _ if angle_depth > 0 => {}
+            // `operator()` is a name, `(anonymous namespace)::` is part of the path
+            '(' if !name.ends_with("operator")
+                && !demangled[i..].starts_with("(anonymous namespace)")
+                && !name.is_empty() =>
+            {
+                break
+            }
+            _ => name.push(c),
+        }
+    }
+
+    name.trim_end().to_string()
+}
@@ -0,0 +1,470 @@
+use crate::analyzer::{get_uncovered_regions, Coverage, RegionCategoryFilter, UncoveredOutput};
+use crate::collector::get_coverage_report_json_by_profdata;
+use crate::config::RunningOptions;
+use crate::error::GetCovError;
+use crate::filter::ReportFilter;
+use crate::pipeline::{merge_parsed_reports, parse_report};
+use llvm_cov_json::CoverageReport;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub struct RegionKey {
+    pub function_name: String,
+    pub file_path: String,
+    pub start_line: u64,
+    pub start_column: u64,
+    pub end_line: u64,
+    pub end_column: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub struct BranchKey {
+    pub function_name: String,
+    pub file_path: String,
+    pub line: u64,
+    pub column: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub struct BranchDirection {
+    pub branch: BranchKey,
+    /// `true` for the true direction of the branch, `false` for the false direction.
+    pub direction: bool,
+}
+
+/// What is known to be covered or uncovered in one run.
+/// A snapshot from a saved GetCov output only lists the partially covered functions and the
+/// uncalled ones, so everything else in it is assumed to be covered, apart from the branches
+/// in the uncovered regions it lists, which were never evaluated. Outputs written before
+/// the uncalled functions were saved can't tell an uncalled function from a covered one, so
+/// the functions they don't list are unknown.
+pub struct CoverageSnapshot {
+    coverage: Coverage,
+    /// False if the snapshot only lists the uncovered parts, see above.
+    complete: bool,
+    /// `None` if the uncalled functions are unknown.
+    uncalled_functions: Option<BTreeSet<String>>,
+    /// The functions with uncovered parts, only used if the snapshot isn't complete.
+    listed_functions: BTreeSet<String>,
+    uncovered_regions: BTreeSet<RegionKey>,
+    /// Whether the true and false direction of each branch were taken.
+    branches: BTreeMap<BranchKey, (bool, bool)>,
+}
+
+impl CoverageSnapshot {
+    pub fn from_report(coverage_report: &CoverageReport) -> Self {
+        let mut uncalled_functions = BTreeSet::new();
+        let mut uncovered_regions = BTreeSet::new();
+        let mut branches: BTreeMap<BranchKey, (bool, bool)> = BTreeMap::new();
+
+        for function in &coverage_report.data[0].functions {
+            for branch in &function.branches {
+                let taken = branches
+                    .entry(BranchKey {
+                        function_name: function.name.to_string(),
+                        file_path: function.filenames[branch.file_id as usize].to_string(),
+                        line: branch.line_start,
+                        column: branch.column_start,
+                    })
+                    .or_default();
+                taken.0 |= branch.execution_count > 0;
+                taken.1 |= branch.false_execution_count > 0;
+            }
+
+            // the regions of uncalled functions are covered by the function list
+            if function.count == 0 {
+                uncalled_functions.insert(function.name.to_string());
+                continue;
+            }
+            for region in get_uncovered_regions(function, &RegionCategoryFilter::default()) {
+                uncovered_regions.insert(RegionKey {
+                    function_name: function.name.to_string(),
+                    file_path: region.file_path,
+                    start_line: region.start_line,
+                    start_column: region.start_column,
+                    end_line: region.end_line,
+                    end_column: region.end_column,
+                });
+            }
+        }
+
+        Self {
+            coverage: Coverage::from_report(coverage_report),
+            complete: true,
+            uncalled_functions: Some(uncalled_functions),
+            listed_functions: BTreeSet::new(),
+            uncovered_regions,
+            branches,
+        }
+    }
+
+    /// Reads the JSON output of the uncovered analysis, i.e., of `getcov` without a mode flag.
+    pub fn from_output_file(path: &Path) -> Result<Self, GetCovError> {
+        let content = fs::read_to_string(path)?;
+        let output: UncoveredOutput = serde_json::from_str(&content)?;
+        let mut listed_functions = BTreeSet::new();
+        let mut uncovered_regions = BTreeSet::new();
+        let mut branches = BTreeMap::new();
+
+        for function in output.uncovered_functions {
+            for predicate in function.partially_covered_predicates {
+                branches.insert(
+                    BranchKey {
+                        function_name: function.function_name.clone(),
+                        file_path: predicate.file_path,
+                        line: predicate.start_line,
+                        column: predicate.start_column,
+                    },
+                    (predicate.true_count > 0, predicate.false_count > 0),
+                );
+            }
+            for region in function.uncovered_regions {
+                uncovered_regions.insert(RegionKey {
+                    function_name: function.function_name.clone(),
+                    file_path: region.file_path,
+                    start_line: region.start_line,
+                    start_column: region.start_column,
+                    end_line: region.end_line,
+                    end_column: region.end_column,
+                });
+            }
+            listed_functions.insert(function.function_name);
+        }
+
+        Ok(Self {
+            coverage: output.coverage,
+            complete: false,
+            uncalled_functions: output
+                .uncalled_functions
+                .map(|names| names.into_iter().collect()),
+            listed_functions,
+            uncovered_regions,
+            branches,
+        })
+    }
+
+    fn is_uncalled(&self, function_name: &str) -> bool {
+        self.uncalled_functions
+            .as_ref()
+            .is_some_and(|uncalled| uncalled.contains(function_name))
+    }
+
+    /// Whether the snapshot knows the regions and branches of the function that weren't
+    /// covered, apart from the ones of an uncalled function.
+    fn knows_function(&self, function_name: &str) -> bool {
+        self.complete
+            || self.uncalled_functions.is_some()
+            || self.listed_functions.contains(function_name)
+    }
+
+    /// Whether a region was left uncovered, `None` if the function of the region is unknown.
+    fn is_uncovered(&self, region: &RegionKey) -> Option<bool> {
+        if self.is_uncalled(&region.function_name) {
+            Some(true)
+        } else if self.knows_function(&region.function_name) {
+            Some(self.uncovered_regions.contains(region))
+        } else {
+            None
+        }
+    }
+
+    /// Checks if the branch starts in one of the uncovered regions of its function.
+    fn in_uncovered_region(&self, branch: &BranchKey) -> bool {
+        let first = RegionKey {
+            function_name: branch.function_name.clone(),
+            file_path: String::new(),
+            start_line: 0,
+            start_column: 0,
+            end_line: 0,
+            end_column: 0,
+        };
+        let position = (branch.line, branch.column);
+        self.uncovered_regions
+            .range(first..)
+            .take_while(|region| region.function_name == branch.function_name)
+            .any(|region| {
+                region.file_path == branch.file_path
+                    && (region.start_line, region.start_column) <= position
+                    && position < (region.end_line, region.end_column)
+            })
+    }
+
+    /// The taken directions of a branch, `None` if the branch is unknown.
+    fn taken(&self, branch: &BranchKey) -> Option<(bool, bool)> {
+        match self.branches.get(branch) {
+            Some(&taken) => Some(taken),
+            None if self.is_uncalled(&branch.function_name) => Some((false, false)),
+            None if !self.complete && self.in_uncovered_region(branch) => Some((false, false)),
+            None if !self.complete && self.knows_function(&branch.function_name) => {
+                Some((true, true))
+            }
+            None => None,
+        }
+    }
+
+    /// The functions uncalled in `before` and called in this snapshot, empty if either
+    /// snapshot doesn't know its uncalled functions.
+    fn newly_called(&self, before: &CoverageSnapshot) -> Vec<String> {
+        match (&before.uncalled_functions, &self.uncalled_functions) {
+            (Some(before), Some(after)) => before.difference(after).cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Loads a saved GetCov JSON output, or reads any other file as a profdata file of the binary.
+/// The filters only apply to profdata files, a saved output is taken as it is.
+pub fn load_snapshot(
+    path: &Path,
+    options: &RunningOptions,
+    report_filter: &ReportFilter,
+) -> Result<CoverageSnapshot, GetCovError> {
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        return CoverageSnapshot::from_output_file(path);
+    }
+
+    if options.binary.is_empty() {
+        return Err(GetCovError::ArgParse(format!(
+            "'{}' is not a GetCov JSON output, comparing profdata files requires the binary",
+            path.display()
+        )));
+    }
+    let coverage_result = get_coverage_report_json_by_profdata(options, path)?;
+    let json = options
+        .path_mapper
+        .remap_report_json(&coverage_result.json)?;
+    let report = parse_report(&json, report_filter)?;
+    let (coverage_report, _) = merge_parsed_reports(vec![report]);
+    Ok(CoverageSnapshot::from_report(&coverage_report))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageDelta {
+    pub covered_branches: i64,
+    pub total_branches: i64,
+    pub covered_functions: i64,
+    pub total_functions: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageDiff {
+    pub base: Coverage,
+    pub head: Coverage,
+    pub delta: CoverageDelta,
+    pub newly_covered_functions: Vec<String>,
+    pub newly_uncovered_functions: Vec<String>,
+    pub newly_covered_regions: Vec<RegionKey>,
+    pub newly_uncovered_regions: Vec<RegionKey>,
+    pub newly_covered_branch_directions: Vec<BranchDirection>,
+    pub newly_uncovered_branch_directions: Vec<BranchDirection>,
+    /// Predicates that always went one way in the base and both ways in the head.
+    pub predicates_now_both_sided: Vec<BranchKey>,
+}
+
+/// Compares two snapshots; "newly covered" is covered in `head` but not in `base`.
+pub fn diff_snapshots(base: CoverageSnapshot, head: CoverageSnapshot) -> CoverageDiff {
+    let delta = CoverageDelta {
+        covered_branches: head.coverage.covered_branches as i64
+            - base.coverage.covered_branches as i64,
+        total_branches: head.coverage.total_branches as i64 - base.coverage.total_branches as i64,
+        covered_functions: head.coverage.covered_functions as i64
+            - base.coverage.covered_functions as i64,
+        total_functions: head.coverage.total_functions as i64
+            - base.coverage.total_functions as i64,
+    };
+
+    let mut newly_covered_branch_directions = Vec::new();
+    let mut newly_uncovered_branch_directions = Vec::new();
+    let mut predicates_now_both_sided = Vec::new();
+
+    let branch_keys: BTreeSet<&BranchKey> =
+        base.branches.keys().chain(head.branches.keys()).collect();
+    for key in branch_keys {
+        let (Some(before), Some(after)) = (base.taken(key), head.taken(key)) else {
+            continue;
+        };
+
+        for (direction, was_taken, is_taken) in
+            [(true, before.0, after.0), (false, before.1, after.1)]
+        {
+            let branch_direction = BranchDirection {
+                branch: key.clone(),
+                direction,
+            };
+            match (was_taken, is_taken) {
+                (false, true) => newly_covered_branch_directions.push(branch_direction),
+                (true, false) => newly_uncovered_branch_directions.push(branch_direction),
+                _ => {}
+            }
+        }
+
+        if before.0 != before.1 && after.0 && after.1 {
+            predicates_now_both_sided.push(key.clone());
+        }
+    }
+
+    let mut newly_covered_regions = Vec::new();
+    let mut newly_uncovered_regions = Vec::new();
+
+    let region_keys: BTreeSet<&RegionKey> = base
+        .uncovered_regions
+        .iter()
+        .chain(head.uncovered_regions.iter())
+        .collect();
+    for key in region_keys {
+        match (base.is_uncovered(key), head.is_uncovered(key)) {
+            (Some(true), Some(false)) => newly_covered_regions.push(key.clone()),
+            (Some(false), Some(true)) => newly_uncovered_regions.push(key.clone()),
+            _ => {}
+        }
+    }
+
+    CoverageDiff {
+        delta,
+        newly_covered_functions: head.newly_called(&base),
+        newly_uncovered_functions: base.newly_called(&head),
+        newly_covered_regions,
+        newly_uncovered_regions,
+        newly_covered_branch_directions,
+        newly_uncovered_branch_directions,
+        predicates_now_both_sided,
+        base: base.coverage,
+        head: head.coverage,
+    }
+}
+
+fn format_delta(delta: i64) -> String {
+    if delta > 0 {
+        format!("+{}", delta)
+    } else {
+        delta.to_string()
+    }
+}
+
+/// Writes the diff as text.
+pub fn write_diff(diff: &CoverageDiff, writer: &mut dyn Write) -> io::Result<()> {
+    writeln!(writer, "Coverage Diff")?;
+    writeln!(writer, "=============\n")?;
+    writeln!(
+        writer,
+        "Branches:  {}/{} -> {}/{} ({} covered)",
+        diff.base.covered_branches,
+        diff.base.total_branches,
+        diff.head.covered_branches,
+        diff.head.total_branches,
+        format_delta(diff.delta.covered_branches)
+    )?;
+    writeln!(
+        writer,
+        "Functions: {}/{} -> {}/{} ({} covered)\n",
+        diff.base.covered_functions,
+        diff.base.total_functions,
+        diff.head.covered_functions,
+        diff.head.total_functions,
+        format_delta(diff.delta.covered_functions)
+    )?;
+
+    write_list(
+        "Newly covered functions",
+        &diff.newly_covered_functions,
+        writer,
+    )?;
+    write_list(
+        "Newly uncovered functions",
+        &diff.newly_uncovered_functions,
+        writer,
+    )?;
+
+    let describe_region = |region: &RegionKey| {
+        format!(
+            "{} ({}:{}:{} - {}:{})",
+            region.function_name,
+            region.file_path,
+            region.start_line,
+            region.start_column,
+            region.end_line,
+            region.end_column
+        )
+    };
+    let describe_branch = |branch: &BranchKey| {
+        format!(
+            "{} ({}:{}:{})",
+            branch.function_name, branch.file_path, branch.line, branch.column
+        )
+    };
+    let describe_direction = |direction: &BranchDirection| {
+        format!(
+            "{}: {}",
+            describe_branch(&direction.branch),
+            direction.direction
+        )
+    };
+
+    write_list(
+        "Newly covered regions",
+        &diff
+            .newly_covered_regions
+            .iter()
+            .map(describe_region)
+            .collect::<Vec<_>>(),
+        writer,
+    )?;
+    write_list(
+        "Newly uncovered regions",
+        &diff
+            .newly_uncovered_regions
+            .iter()
+            .map(describe_region)
+            .collect::<Vec<_>>(),
+        writer,
+    )?;
+    write_list(
+        "Newly covered branch directions",
+        &diff
+            .newly_covered_branch_directions
+            .iter()
+            .map(describe_direction)
+            .collect::<Vec<_>>(),
+        writer,
+    )?;
+    write_list(
+        "Newly uncovered branch directions",
+        &diff
+            .newly_uncovered_branch_directions
+            .iter()
+            .map(describe_direction)
+            .collect::<Vec<_>>(),
+        writer,
+    )?;
+    write_list(
+        "Predicates now taken both ways",
+        &diff
+            .predicates_now_both_sided
+            .iter()
+            .map(describe_branch)
+            .collect::<Vec<_>>(),
+        writer,
+    )?;
+    Ok(())
+}
+
+fn write_list(title: &str, items: &[String], writer: &mut dyn Write) -> io::Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "{} ({}):", title, items.len())?;
+    for item in items {
+        writeln!(writer, "  {}", item)?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use super::{branches_by_line, mapped_lines};
+use llvm_cov_json::{CoverageReport, FileMetrics};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The line and branch counts of a class, package or the whole report.
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    lines_covered: u64,
+    lines_valid: u64,
+    branches_covered: u64,
+    branches_valid: u64,
+}
+
+impl Counts {
+    fn add(&mut self, other: Counts) {
+        self.lines_covered += other.lines_covered;
+        self.lines_valid += other.lines_valid;
+        self.branches_covered += other.branches_covered;
+        self.branches_valid += other.branches_valid;
+    }
+
+    fn line_rate(&self) -> String {
+        rate(self.lines_covered, self.lines_valid)
+    }
+
+    fn branch_rate(&self) -> String {
+        rate(self.branches_covered, self.branches_valid)
+    }
+}
+
+fn rate(covered: u64, valid: u64) -> String {
+    if valid == 0 {
+        "1".to_string()
+    } else {
+        format!("{:.4}", covered as f64 / valid as f64)
+    }
+}
+
+/// One `<line>` of a class, with the covered and total branch directions on it.
+struct Line {
+    number: u64,
+    hits: u64,
+    branches: Option<(u64, u64)>,
+}
+
+/// One source file, which Cobertura calls a class.
+struct Class {
+    name: String,
+    filename: String,
+    lines: Vec<Line>,
+    counts: Counts,
+}
+
+/// Writes the report as a Cobertura XML document. Every source file is a class, grouped into
+/// packages by its directory relative to the common directory of all files. Lines with branches
+/// get a `condition-coverage` attribute counting both directions of each branch on the line.
+pub fn write_cobertura(coverage_report: &CoverageReport, writer: &mut dyn Write) -> io::Result<()> {
+    let files = &coverage_report.data[0].files;
+    let source = common_directory(files.iter().map(|file| Path::new(file.filename)));
+
+    let mut packages: BTreeMap<String, Vec<Class>> = BTreeMap::new();
+    for file in files {
+        let class = to_class(file, &source);
+        let package = Path::new(&class.filename)
+            .parent()
+            .map(|parent| parent.to_string_lossy().replace(['/', '\\'], "."))
+            .unwrap_or_default();
+        packages.entry(package).or_default().push(class);
+    }
+
+    let mut total = Counts::default();
+    for class in packages.values().flatten() {
+        total.add(class.counts);
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    writeln!(writer, r#"<?xml version="1.0" ?>"#)?;
+    writeln!(
+        writer,
+        r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+    )?;
+    writeln!(
+        writer,
+        r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="getcov" timestamp="{}">"#,
+        total.line_rate(),
+        total.branch_rate(),
+        total.lines_covered,
+        total.lines_valid,
+        total.branches_covered,
+        total.branches_valid,
+        timestamp
+    )?;
+    writeln!(writer, "  <sources>")?;
+    writeln!(
+        writer,
+        "    <source>{}</source>",
+        escape(&source.to_string_lossy())
+    )?;
+    writeln!(writer, "  </sources>")?;
+
+    writeln!(writer, "  <packages>")?;
+    for (name, classes) in &packages {
+        write_package(name, classes, writer)?;
+    }
+    writeln!(writer, "  </packages>")?;
+    writeln!(writer, "</coverage>")
+}
+
+fn write_package(name: &str, classes: &[Class], writer: &mut dyn Write) -> io::Result<()> {
+    let mut counts = Counts::default();
+    for class in classes {
+        counts.add(class.counts);
+    }
+
+    writeln!(
+        writer,
+        r#"    <package name="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+        escape(name),
+        counts.line_rate(),
+        counts.branch_rate()
+    )?;
+    writeln!(writer, "      <classes>")?;
+    for class in classes {
+        writeln!(
+            writer,
+            r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+            escape(&class.name),
+            escape(&class.filename),
+            class.counts.line_rate(),
+            class.counts.branch_rate()
+        )?;
+        writeln!(writer, "          <methods/>")?;
+        writeln!(writer, "          <lines>")?;
+        for line in &class.lines {
+            match line.branches {
+                Some((covered, total)) => writeln!(
+                    writer,
+                    r#"            <line number="{}" hits="{}" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+                    line.number,
+                    line.hits,
+                    covered * 100 / total,
+                    covered,
+                    total
+                )?,
+                None => writeln!(
+                    writer,
+                    r#"            <line number="{}" hits="{}" branch="false"/>"#,
+                    line.number, line.hits
+                )?,
+            }
+        }
+        writeln!(writer, "          </lines>")?;
+        writeln!(writer, "        </class>")?;
+    }
+    writeln!(writer, "      </classes>")?;
+    writeln!(writer, "    </package>")
+}
+
+/// Collects the instrumented lines of the file and the branch directions taken on each of them.
+fn to_class(file: &FileMetrics, source: &Path) -> Class {
+    let path = Path::new(file.filename);
+    let filename = path.strip_prefix(source).unwrap_or(path);
+    let branches = branches_by_line(file);
+
+    let mut counts = Counts::default();
+    let lines = mapped_lines(file)
+        .into_iter()
+        .map(|(number, hits)| {
+            let branches = branches.get(&number).map(|branches| {
+                let covered: u64 = branches
+                    .iter()
+                    .map(|(true_count, false_count)| {
+                        (*true_count > 0) as u64 + (*false_count > 0) as u64
+                    })
+                    .sum();
+                (covered, branches.len() as u64 * 2)
+            });
+
+            counts.lines_valid += 1;
+            counts.lines_covered += (hits > 0) as u64;
+            if let Some((covered, total)) = branches {
+                counts.branches_covered += covered;
+                counts.branches_valid += total;
+            }
+            Line {
+                number,
+                hits,
+                branches,
+            }
+        })
+        .collect();
+
+    Class {
+        name: filename
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string()),
+        filename: filename.to_string_lossy().to_string(),
+        lines,
+        counts,
+    }
+}
+
+/// Returns the deepest directory that contains all the paths.
+fn common_directory<'a>(mut paths: impl Iterator<Item = &'a Path>) -> PathBuf {
+    let Some(first) = paths.next() else {
+        return PathBuf::new();
+    };
+    let mut common = first.parent().unwrap_or(first).to_path_buf();
+    for path in paths {
+        while !path.starts_with(&common) {
+            if !common.pop() {
+                break;
+            }
+        }
+    }
+    common
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,470 @@
+use super::{branches_by_line, evaluations, mapped_lines};
+use crate::analyzer::{
+    get_uncovered, tokenize, LineCoverageMap, PartiallyCoveredFunction, RegionCategoryFilter,
+    TokenKind,
+};
+use crate::merge::source_location;
+use llvm_cov_json::{CoverageReport, FileMetrics, FunctionMetrics};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const STYLE: &str = r#"body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; }
+th, td { padding: 2px 8px; text-align: left; }
+th { border-bottom: 2px solid #888; }
+tr.partial td { background: #fff4e0; }
+td.number { text-align: right; font-family: monospace; }
+a { color: #0550ae; text-decoration: none; }
+a:hover { text-decoration: underline; }
+table.source { font-family: monospace; white-space: pre; width: 100%; }
+table.source td { padding: 0 8px; }
+table.source td.line-number { color: #888; text-align: right; user-select: none; }
+table.source td.count { text-align: right; border-right: 1px solid #ccc; }
+table.source tr.covered td.count { background: #dafbe1; }
+table.source tr.uncovered td.count { background: #ffd7d5; }
+table.source tr:target { background: #fff8c5; }
+.keyword { color: #cf222e; }
+.literal { color: #0a3069; }
+.num-literal { color: #0550ae; }
+.comment { color: #6e7781; font-style: italic; }
+.preprocessor { color: #8250df; }
+.uncovered-region { background: #ffd7d5; }
+.one-sided { background: #ffe58f; outline: 1px solid #d4a72c; }
+"#;
+
+const KEYWORDS: [&str; 64] = [
+    "alignas",
+    "alignof",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "constexpr",
+    "const_cast",
+    "continue",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "dynamic_cast",
+    "else",
+    "enum",
+    "explicit",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "nullptr",
+    "operator",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "reinterpret_cast",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "static_cast",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+];
+
+/// A range of a source file to mark, with the tooltip to show on it.
+struct Highlight {
+    start: (u64, u64),
+    end: (u64, u64),
+    class: &'static str,
+    title: String,
+}
+
+/// Writes a static HTML report into the directory: an `index.html` with the coverage of every
+/// file and function, and a page per source file with its line counts, on which the one-sided
+/// predicates and uncovered regions of the uncovered analysis are marked. Everything, including
+/// the style sheet, is written locally, so the report works offline.
+pub fn write_html(
+    coverage_report: &CoverageReport,
+    category_filter: &RegionCategoryFilter,
+    dir: &Path,
+) -> io::Result<()> {
+    let line_coverage = LineCoverageMap::new(coverage_report);
+    let uncovered_functions = get_uncovered(coverage_report, &line_coverage, category_filter);
+
+    let mut files: Vec<&FileMetrics> = coverage_report.data[0].files.iter().collect();
+    files.sort_by_key(|file| file.filename);
+    let pages: HashMap<&str, String> = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (file.filename, format!("source-{}.html", i)))
+        .collect();
+
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("style.css"), STYLE)?;
+    fs::write(
+        dir.join("index.html"),
+        index_page(coverage_report, &files, &pages, &uncovered_functions),
+    )?;
+    for file in &files {
+        fs::write(
+            dir.join(&pages[file.filename]),
+            source_page(file, &uncovered_functions),
+        )?;
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn percent(covered: u64, total: u64) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", covered as f64 * 100.0 / total as f64)
+    }
+}
+
+fn header(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n",
+        escape(title)
+    )
+}
+
+/// The covered and total branch directions of a function.
+fn branch_directions(function: &FunctionMetrics) -> (u64, u64) {
+    let covered = function
+        .branches
+        .iter()
+        .map(|branch| {
+            (branch.execution_count > 0) as u64 + (branch.false_execution_count > 0) as u64
+        })
+        .sum();
+    (covered, function.branches.len() as u64 * 2)
+}
+
+fn index_page(
+    coverage_report: &CoverageReport,
+    files: &[&FileMetrics],
+    pages: &HashMap<&str, String>,
+    uncovered_functions: &[PartiallyCoveredFunction],
+) -> String {
+    let mut html = header("GetCov Report");
+    html.push_str("<h1>GetCov Report</h1>\n");
+
+    let functions = &coverage_report.data[0].functions;
+    // the number of called and of all functions defined in each file
+    let mut function_counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for function in functions {
+        if let Some((path, _, _)) = source_location(function) {
+            let (called, defined) = function_counts.entry(path).or_default();
+            *called += (function.count > 0) as usize;
+            *defined += 1;
+        }
+    }
+
+    let (mut covered_lines, mut total_lines) = (0, 0);
+    let (mut covered_branches, mut total_branches) = (0, 0);
+    let mut file_rows = String::new();
+    for file in files {
+        let lines = mapped_lines(file);
+        let covered = lines.iter().filter(|(_, count)| *count > 0).count() as u64;
+        let (branches_covered, branches_total) = branches_by_line(file).values().flatten().fold(
+            (0, 0),
+            |(covered, total), (true_count, false_count)| {
+                (
+                    covered + (*true_count > 0) as u64 + (*false_count > 0) as u64,
+                    total + 2,
+                )
+            },
+        );
+        let (called, defined) = function_counts
+            .get(file.filename)
+            .copied()
+            .unwrap_or_default();
+
+        covered_lines += covered;
+        total_lines += lines.len() as u64;
+        covered_branches += branches_covered;
+        total_branches += branches_total;
+        let _ = writeln!(
+            file_rows,
+            "<tr><td><a href=\"{}\">{}</a></td><td class=\"number\">{}</td>\
+             <td class=\"number\">{}</td><td class=\"number\">{}</td></tr>",
+            pages[file.filename],
+            escape(file.filename),
+            percent(covered, lines.len() as u64),
+            percent(branches_covered, branches_total),
+            percent(called as u64, defined as u64)
+        );
+    }
+
+    let called = functions
+        .iter()
+        .filter(|function| function.count > 0)
+        .count() as u64;
+    let _ = writeln!(
+        html,
+        "<p>Lines: {} ({}/{}), branches: {} ({}/{}), functions: {} ({}/{})</p>",
+        percent(covered_lines, total_lines),
+        covered_lines,
+        total_lines,
+        percent(covered_branches, total_branches),
+        covered_branches,
+        total_branches,
+        percent(called, functions.len() as u64),
+        called,
+        functions.len()
+    );
+
+    html.push_str("<h2>Files</h2>\n<table>\n");
+    html.push_str("<tr><th>File</th><th>Lines</th><th>Branches</th><th>Functions</th></tr>\n");
+    html.push_str(&file_rows);
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Functions</h2>\n");
+    html.push_str(
+        "<p>Highlighted functions were called, but have one-sided predicates.</p>\n<table>\n",
+    );
+    html.push_str(
+        "<tr><th>Function</th><th>Location</th><th>Calls</th><th>Branches</th>\
+         <th>One-sided predicates</th><th>Uncovered regions</th></tr>\n",
+    );
+    let partial: HashMap<(&str, u64), &PartiallyCoveredFunction> = uncovered_functions
+        .iter()
+        .filter_map(|function| {
+            let whole_function = function.whole_function.as_ref()?;
+            Some((
+                (whole_function.file_path.as_str(), whole_function.start_line),
+                function,
+            ))
+        })
+        .collect();
+    let mut rows: Vec<((String, u64), &FunctionMetrics)> = functions
+        .iter()
+        .filter_map(|function| {
+            let (path, line, _) = source_location(function)?;
+            Some(((path, line), function))
+        })
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for ((path, line), function) in rows {
+        let (covered, total) = branch_directions(function);
+        let uncovered = partial.get(&(path.as_str(), line));
+        let link = match pages.get(path.as_str()) {
+            Some(page) => format!(
+                "<a href=\"{}#L{}\">{}:{}</a>",
+                page,
+                line,
+                escape(&path),
+                line
+            ),
+            None => format!("{}:{}", escape(&path), line),
+        };
+        let _ = writeln!(
+            html,
+            "<tr{}><td>{}</td><td>{}</td><td class=\"number\">{}</td>\
+             <td class=\"number\">{}</td><td class=\"number\">{}</td>\
+             <td class=\"number\">{}</td></tr>",
+            if uncovered.is_some() {
+                " class=\"partial\""
+            } else {
+                ""
+            },
+            escape(&function.name),
+            link,
+            function.count,
+            percent(covered, total),
+            uncovered.map_or(0, |function| function.partially_covered_predicates.len()),
+            uncovered.map_or(0, |function| function.uncovered_regions.len())
+        );
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// The one-sided predicates and uncovered regions in the file.
+fn highlights(file_path: &str, uncovered_functions: &[PartiallyCoveredFunction]) -> Vec<Highlight> {
+    let mut highlights = Vec::new();
+    for function in uncovered_functions {
+        for region in &function.uncovered_regions {
+            if region.file_path == file_path {
+                highlights.push(Highlight {
+                    start: (region.start_line, region.start_column),
+                    end: (region.end_line, region.end_column),
+                    class: "uncovered-region",
+                    title: match region.category {
+                        Some(category) => format!("Never executed ({})", category.name()),
+                        None => "Never executed".to_string(),
+                    },
+                });
+            }
+        }
+        for predicate in &function.partially_covered_predicates {
+            if predicate.file_path == file_path {
+                highlights.push(Highlight {
+                    start: (predicate.start_line, predicate.start_column),
+                    end: (predicate.end_line, predicate.end_column),
+                    class: "one-sided",
+                    title: if predicate.false_count == 0 {
+                        format!("Always true ({})", evaluations(predicate.true_count))
+                    } else {
+                        format!("Always false ({})", evaluations(predicate.false_count))
+                    },
+                });
+            }
+        }
+    }
+    highlights
+}
+
+/// Returns the byte offset of a 1-based line and column, clamped to the line.
+fn offset(source: &str, line_starts: &[usize], (line, column): (u64, u64)) -> usize {
+    let Some(&line_start) = line_starts.get(line.saturating_sub(1) as usize) else {
+        return source.len();
+    };
+    let line_end = source[line_start..]
+        .find('\n')
+        .map_or(source.len(), |pos| line_start + pos);
+    let mut offset = (line_start + column.saturating_sub(1) as usize).min(line_end);
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn source_page(file: &FileMetrics, uncovered_functions: &[PartiallyCoveredFunction]) -> String {
+    let mut html = header(file.filename);
+    let _ = writeln!(
+        html,
+        "<p><a href=\"index.html\">Index</a></p>\n<h1>{}</h1>",
+        escape(file.filename)
+    );
+
+    let Ok(source) = fs::read_to_string(file.filename) else {
+        html.push_str("<p>The source file could not be read.</p>\n</body>\n</html>\n");
+        return html;
+    };
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+
+    // the syntax class of every byte, and the highlight it is part of
+    let mut classes: Vec<Option<&str>> = vec![None; source.len()];
+    for token in tokenize(&source) {
+        let class = match token.kind {
+            TokenKind::Identifier if KEYWORDS.contains(&token.text) => "keyword",
+            TokenKind::String | TokenKind::Char => "literal",
+            TokenKind::Number => "num-literal",
+            TokenKind::Comment => "comment",
+            TokenKind::Preprocessor => "preprocessor",
+            _ => continue,
+        };
+        classes[token.start..token.end].fill(Some(class));
+    }
+    let highlights = highlights(file.filename, uncovered_functions);
+    let mut marks: Vec<Option<usize>> = vec![None; source.len()];
+    // predicates are added last, so they are shown on top of the regions they are in
+    for (i, highlight) in highlights.iter().enumerate() {
+        let start = offset(&source, &line_starts, highlight.start);
+        let end = offset(&source, &line_starts, highlight.end);
+        if start < end {
+            marks[start..end].fill(Some(i));
+        }
+    }
+
+    let counts: HashMap<u64, u64> = mapped_lines(file).into_iter().collect();
+    html.push_str("<table class=\"source\">\n");
+    for (i, &line_start) in line_starts.iter().enumerate() {
+        let line_end = source[line_start..]
+            .find('\n')
+            .map_or(source.len(), |pos| line_start + pos);
+        if line_start == source.len() {
+            break;
+        }
+        let number = i as u64 + 1;
+        let (row_class, count) = match counts.get(&number) {
+            Some(0) => (" class=\"uncovered\"", "0".to_string()),
+            Some(count) => (" class=\"covered\"", count.to_string()),
+            None => ("", String::new()),
+        };
+
+        let _ = write!(
+            html,
+            "<tr id=\"L{0}\"{1}><td class=\"line-number\"><a href=\"#L{0}\">{0}</a></td>\
+             <td class=\"count\">{2}</td><td>",
+            number, row_class, count
+        );
+        // one span for every run of bytes with the same class and highlight
+        let mut run_start = line_start;
+        for j in line_start..=line_end {
+            if j < line_end && (classes[j], marks[j]) == (classes[run_start], marks[run_start])
+                || !source.is_char_boundary(j)
+            {
+                continue;
+            }
+            if run_start < j {
+                let text = escape(source[run_start..j].trim_end_matches('\r'));
+                let mut class: Vec<&str> = classes[run_start].into_iter().collect();
+                let mut title = String::new();
+                if let Some(highlight) = marks[run_start].map(|k| &highlights[k]) {
+                    class.push(highlight.class);
+                    title = format!(" title=\"{}\"", escape(&highlight.title));
+                }
+                if class.is_empty() {
+                    html.push_str(&text);
+                } else {
+                    let _ = write!(
+                        html,
+                        "<span class=\"{}\"{}>{}</span>",
+                        class.join(" "),
+                        title,
+                        text
+                    );
+                }
+            }
+            run_start = j;
+        }
+        html.push_str("</td></tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
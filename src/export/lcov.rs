@@ -0,0 +1,101 @@
+use super::{branches_by_line, mapped_lines};
+use llvm_cov_json::{CoverageReport, FileMetrics, RegionKind};
+use std::io::{self, Write};
+
+/// Writes the report as an LCOV tracefile, as `llvm-cov export -format=lcov` does.
+/// Line counts are derived from the segments of each file, and every branch gets one
+/// `BRDA` record per direction.
+pub fn write_lcov(coverage_report: &CoverageReport, writer: &mut dyn Write) -> io::Result<()> {
+    for file in &coverage_report.data[0].files {
+        writeln!(writer, "TN:")?;
+        writeln!(writer, "SF:{}", file.filename)?;
+        write_functions(coverage_report, file, writer)?;
+        write_branches(file, writer)?;
+        write_lines(file, writer)?;
+        writeln!(writer, "end_of_record")?;
+    }
+    Ok(())
+}
+
+/// The name of a function in the `FN` and `FNDA` records, which end at the first comma after
+/// the count. llvm-cov writes the mangled name, but the report only has the demangled one, so
+/// the commas between parameters and template arguments are left out.
+fn lcov_name(name: &str) -> String {
+    name.replace(',', "")
+}
+
+/// Writes the `FN` and `FNDA` records of the functions defined in the file.
+fn write_functions(
+    coverage_report: &CoverageReport,
+    file: &FileMetrics,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let functions: Vec<(u64, String, u64)> = coverage_report.data[0]
+        .functions
+        .iter()
+        .filter_map(|function| {
+            let region = function
+                .regions
+                .iter()
+                .find(|region| region.kind == RegionKind::Code)?;
+            (function.filenames[region.file_id as usize] == file.filename).then_some((
+                region.line_start,
+                lcov_name(&function.name),
+                function.count,
+            ))
+        })
+        .collect();
+
+    for (line, name, _) in &functions {
+        writeln!(writer, "FN:{},{}", line, name)?;
+    }
+    for (_, name, count) in &functions {
+        writeln!(writer, "FNDA:{},{}", count, name)?;
+    }
+    writeln!(writer, "FNF:{}", functions.len())?;
+    writeln!(
+        writer,
+        "FNH:{}",
+        functions.iter().filter(|(_, _, count)| *count > 0).count()
+    )
+}
+
+/// Writes a `BRDA` record for each direction of every branch. The block number is the index
+/// of the branch on its line, the branch number is 0 for true and 1 for false.
+fn write_branches(file: &FileMetrics, writer: &mut dyn Write) -> io::Result<()> {
+    let mut found = 0;
+    let mut hit = 0;
+    for (line, branches) in branches_by_line(file) {
+        for (block, (true_count, false_count)) in branches.into_iter().enumerate() {
+            for (direction, count) in [true_count, false_count].into_iter().enumerate() {
+                // `-` marks a branch that was never evaluated
+                let taken = if true_count == 0 && false_count == 0 {
+                    "-".to_string()
+                } else {
+                    count.to_string()
+                };
+                writeln!(writer, "BRDA:{},{},{},{}", line, block, direction, taken)?;
+                found += 1;
+                hit += (count > 0) as u64;
+            }
+        }
+    }
+
+    writeln!(writer, "BRF:{}", found)?;
+    writeln!(writer, "BRH:{}", hit)
+}
+
+/// Writes a `DA` record for every instrumented line.
+fn write_lines(file: &FileMetrics, writer: &mut dyn Write) -> io::Result<()> {
+    let lines = mapped_lines(file);
+
+    for (line, count) in &lines {
+        writeln!(writer, "DA:{},{}", line, count)?;
+    }
+    writeln!(writer, "LF:{}", lines.len())?;
+    writeln!(
+        writer,
+        "LH:{}",
+        lines.iter().filter(|(_, count)| *count > 0).count()
+    )
+}
@@ -0,0 +1,68 @@
+mod cobertura;
+mod html;
+mod lcov;
+mod sarif;
+
+pub use cobertura::write_cobertura;
+pub use html::write_html;
+pub use lcov::write_lcov;
+pub use sarif::write_sarif;
+
+use llvm_cov_json::FileMetrics;
+use std::collections::BTreeMap;
+
+/// Hashes the parts with FNV-1a, whose values, unlike those of `DefaultHasher`, do not change
+/// between Rust versions, so they can identify findings across runs.
+pub fn stable_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in part.bytes().chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Describes how often a predicate was evaluated, e.g., `3 evaluations`.
+pub fn evaluations(count: u64) -> String {
+    if count == 1 {
+        "1 evaluation".to_string()
+    } else {
+        format!("{} evaluations", count)
+    }
+}
+
+/// Returns the line number and execution count of every instrumented line of the file.
+fn mapped_lines(file: &FileMetrics) -> Vec<(u64, u64)> {
+    file.line_coverage()
+        .into_iter()
+        .filter(|line| line.mapped)
+        .map(|line| (line.line, line.execution_count))
+        .collect()
+}
+
+/// Returns the true and false counts of the branches of the file by line,
+/// ordered by their column on the line.
+fn branches_by_line(file: &FileMetrics) -> BTreeMap<u64, Vec<(u64, u64)>> {
+    let mut lines: BTreeMap<u64, Vec<(u64, u64, u64)>> = BTreeMap::new();
+    for branch in &file.branches {
+        lines.entry(branch.line_start).or_default().push((
+            branch.column_start,
+            branch.execution_count,
+            branch.false_execution_count,
+        ));
+    }
+
+    lines
+        .into_iter()
+        .map(|(line, mut branches)| {
+            branches.sort_unstable();
+            let counts = branches
+                .into_iter()
+                .map(|(_, true_count, false_count)| (true_count, false_count))
+                .collect();
+            (line, counts)
+        })
+        .collect()
+}
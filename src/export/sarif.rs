@@ -0,0 +1,380 @@
+use super::{evaluations, stable_hash};
+use crate::analyzer::{
+    get_file_part, CodeRegion, ExpansionSite, PartiallyCoveredPredicate, UncoveredOutput,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The kinds of findings, in the order of the rules of the driver.
+const RULES: [(&str, &str, &str, &str); 3] = [
+    (
+        "always-true",
+        "PredicateAlwaysTrue",
+        "Predicate is always true",
+        "The predicate was evaluated, but never to false.",
+    ),
+    (
+        "always-false",
+        "PredicateAlwaysFalse",
+        "Predicate is always false",
+        "The predicate was evaluated, but never to true.",
+    ),
+    (
+        "uncovered-region",
+        "UncoveredRegion",
+        "Uncovered region",
+        "The region of a function that was called was never executed.",
+    ),
+];
+
+#[derive(Serialize)]
+struct Log {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [Run; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    original_uri_base_ids: serde_json::Value,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    name: &'static str,
+    short_description: Message,
+    full_description: Message,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    partial_fingerprints: Fingerprints,
+    properties: Properties,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: u64,
+    start_column: u64,
+    end_line: u64,
+    end_column: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<Message>,
+}
+
+/// Identifies a result independently of its line, so a baseline still matches it after the
+/// code above it changed.
+#[derive(Serialize)]
+struct Fingerprints {
+    #[serde(rename = "getcovFinding/v1")]
+    finding: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Properties {
+    function_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    true_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    false_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<&'static str>,
+}
+
+/// Writes the partially covered predicates and uncovered regions of the uncovered analysis as
+/// a SARIF 2.1.0 log. Paths below the working directory are relative to `%SRCROOT%`, and the
+/// results are sorted by location, so the logs of two runs can be compared as they are.
+pub fn write_sarif(output: &UncoveredOutput, writer: &mut dyn Write) -> io::Result<()> {
+    let root = std::env::current_dir()?;
+
+    let mut results = Vec::new();
+    for function in &output.uncovered_functions {
+        for predicate in &function.partially_covered_predicates {
+            results.push(predicate_result(&function.function_name, predicate, &root));
+        }
+        for region in &function.uncovered_regions {
+            results.push(region_result(&function.function_name, region, &root));
+        }
+    }
+    results.sort_by(|a, b| {
+        let key = |result: &SarifResult| {
+            let location = &result.locations[0].physical_location;
+            (
+                location.artifact_location.uri.clone(),
+                location.region.start_line,
+                location.region.start_column,
+                result.rule_index,
+            )
+        };
+        key(a).cmp(&key(b))
+    });
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for result in &mut results {
+        let fingerprint = &mut result.partial_fingerprints.finding;
+        let occurrence = occurrences.entry(fingerprint.clone()).or_default();
+        *occurrence += 1;
+        *fingerprint = format!("{}:{}", fingerprint, occurrence);
+    }
+
+    let log = Log {
+        schema: SCHEMA,
+        version: "2.1.0",
+        runs: [Run {
+            tool: Tool {
+                driver: Driver {
+                    name: "getcov",
+                    version: env!("CARGO_PKG_VERSION"),
+                    rules: RULES
+                        .iter()
+                        .map(|&(id, name, summary, description)| Rule {
+                            id,
+                            name,
+                            short_description: Message {
+                                text: summary.to_string(),
+                            },
+                            full_description: Message {
+                                text: description.to_string(),
+                            },
+                        })
+                        .collect(),
+                },
+            },
+            original_uri_base_ids: serde_json::json!({
+                "%SRCROOT%": { "uri": format!("file://{}/", root.display()) }
+            }),
+            results,
+        }],
+    };
+
+    serde_json::to_writer_pretty(&mut *writer, &log)?;
+    writeln!(writer)
+}
+
+fn predicate_result(
+    function_name: &str,
+    predicate: &PartiallyCoveredPredicate,
+    root: &Path,
+) -> SarifResult {
+    let (rule_index, message) = if predicate.false_count == 0 {
+        (
+            0,
+            format!(
+                "Predicate is always true in `{}` ({})",
+                function_name,
+                evaluations(predicate.true_count)
+            ),
+        )
+    } else {
+        (
+            1,
+            format!(
+                "Predicate is always false in `{}` ({})",
+                function_name,
+                evaluations(predicate.false_count)
+            ),
+        )
+    };
+    let location = location(
+        &predicate.file_path,
+        (predicate.start_line, predicate.start_column),
+        (predicate.end_line, predicate.end_column),
+        root,
+    );
+
+    SarifResult {
+        rule_id: RULES[rule_index].0,
+        rule_index,
+        level: "warning",
+        message: Message { text: message },
+        partial_fingerprints: fingerprint(RULES[rule_index].0, function_name, &location),
+        locations: vec![location],
+        related_locations: expansion_locations(&predicate.expansions, root),
+        properties: Properties {
+            function_name: function_name.to_string(),
+            true_count: Some(predicate.true_count),
+            false_count: Some(predicate.false_count),
+            category: None,
+        },
+    }
+}
+
+fn region_result(function_name: &str, region: &CodeRegion, root: &Path) -> SarifResult {
+    let location = location(
+        &region.file_path,
+        (region.start_line, region.start_column),
+        (region.end_line, region.end_column),
+        root,
+    );
+
+    SarifResult {
+        rule_id: RULES[2].0,
+        rule_index: 2,
+        // downweighted regions are listed, but should not stand out
+        level: if region.downweighted {
+            "note"
+        } else {
+            "warning"
+        },
+        message: Message {
+            text: format!("Region of `{}` was never executed", function_name),
+        },
+        partial_fingerprints: fingerprint(RULES[2].0, function_name, &location),
+        locations: vec![location],
+        related_locations: expansion_locations(&region.expansions, root),
+        properties: Properties {
+            function_name: function_name.to_string(),
+            true_count: None,
+            false_count: None,
+            category: region.category.map(|category| category.name()),
+        },
+    }
+}
+
+/// The physical location of the source range, with the source code as its snippet.
+fn location(file_path: &str, start: (u64, u64), end: (u64, u64), root: &Path) -> Location {
+    let snippet = Path::new(file_path)
+        .exists()
+        .then(|| get_file_part(file_path, start.0, start.1, end.0, end.1));
+
+    Location {
+        physical_location: PhysicalLocation {
+            artifact_location: artifact_location(file_path, root),
+            region: Region {
+                start_line: start.0,
+                start_column: start.1,
+                end_line: end.0,
+                end_column: end.1,
+                snippet: snippet.map(|text| Message { text }),
+            },
+        },
+        message: None,
+    }
+}
+
+fn artifact_location(file_path: &str, root: &Path) -> ArtifactLocation {
+    match Path::new(file_path).strip_prefix(root) {
+        Ok(relative) => ArtifactLocation {
+            uri: relative.to_string_lossy().replace('\\', "/"),
+            uri_base_id: Some("%SRCROOT%"),
+        },
+        Err(_) => ArtifactLocation {
+            uri: file_path.to_string(),
+            uri_base_id: None,
+        },
+    }
+}
+
+/// The use sites of the macros a finding was expanded from, innermost first.
+fn expansion_locations(expansions: &[ExpansionSite], root: &Path) -> Vec<Location> {
+    expansions
+        .iter()
+        .map(|site| Location {
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(&site.file_path, root),
+                region: Region {
+                    start_line: site.line,
+                    start_column: site.column,
+                    end_line: site.line,
+                    end_column: site.column,
+                    snippet: None,
+                },
+            },
+            message: Some(Message {
+                text: match &site.macro_name {
+                    Some(name) => format!("Expanded from macro `{}`", name),
+                    None => "Expanded from here".to_string(),
+                },
+            }),
+        })
+        .collect()
+}
+
+/// Hashes the rule, the function and the normalized source code of the finding. The number of
+/// the occurrence is appended later, as the same code can appear several times in one function.
+fn fingerprint(rule_id: &str, function_name: &str, location: &Location) -> Fingerprints {
+    let physical_location = &location.physical_location;
+    let snippet = physical_location
+        .region
+        .snippet
+        .as_ref()
+        .map(|snippet| {
+            snippet
+                .text
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+
+    let hash = stable_hash(&[
+        rule_id,
+        function_name,
+        &physical_location.artifact_location.uri,
+        &snippet,
+    ]);
+
+    Fingerprints {
+        finding: format!("{:016x}", hash),
+    }
+}
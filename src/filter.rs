@@ -0,0 +1,92 @@
+use llvm_cov_json::{CoverageReport, FunctionMetrics, RegionKind};
+use regex::Regex;
+
+/// Regexes selecting the files and functions to analyze. An empty include list selects
+/// everything; a name is dropped if it matches any exclude regex. Regexes match anywhere
+/// in the file path or function name.
+#[derive(Debug, Default)]
+pub struct ReportFilter {
+    pub include_files: Vec<Regex>,
+    pub exclude_files: Vec<Regex>,
+    pub include_functions: Vec<Regex>,
+    pub exclude_functions: Vec<Regex>,
+}
+
+/// The file of a function and the line and column it starts and ends at.
+type FunctionSpan<'a> = (&'a str, (u64, u64), (u64, u64));
+
+fn is_selected(name: &str, include: &[Regex], exclude: &[Regex]) -> bool {
+    (include.is_empty() || include.iter().any(|re| re.is_match(name)))
+        && !exclude.iter().any(|re| re.is_match(name))
+}
+
+impl ReportFilter {
+    pub fn is_file_selected(&self, file_path: &str) -> bool {
+        is_selected(file_path, &self.include_files, &self.exclude_files)
+    }
+
+    /// A function is selected if both its name and the file it is defined in are.
+    pub fn is_function_selected(&self, function: &FunctionMetrics) -> bool {
+        let file_selected = function
+            .regions
+            .first()
+            .map(|region| self.is_file_selected(function.filenames[region.file_id as usize]))
+            .unwrap_or(true);
+
+        file_selected
+            && is_selected(
+                &function.name,
+                &self.include_functions,
+                &self.exclude_functions,
+            )
+    }
+
+    /// Removes the files and functions that are not selected from the report. The lines and
+    /// branches of the removed functions are removed from their files as well.
+    /// The summaries in the report are left as they are and describe the unfiltered data.
+    pub fn apply(&self, coverage_report: &mut CoverageReport) {
+        for export in &mut coverage_report.data {
+            export
+                .files
+                .retain(|file| self.is_file_selected(file.filename));
+
+            let mut removed_spans: Vec<FunctionSpan> = Vec::new();
+            export.functions.retain(|function| {
+                let selected = self.is_function_selected(function);
+                if !selected {
+                    if let Some(region) = function
+                        .regions
+                        .iter()
+                        .find(|region| region.kind == RegionKind::Code)
+                    {
+                        removed_spans.push((
+                            function.filenames[region.file_id as usize],
+                            (region.line_start, region.column_start),
+                            (region.line_end, region.column_end),
+                        ));
+                    }
+                }
+                selected
+            });
+
+            for (file_path, start, end) in removed_spans {
+                let Some(file) = export
+                    .files
+                    .iter_mut()
+                    .find(|file| file.filename == file_path)
+                else {
+                    continue;
+                };
+                file.branches.retain(|branch| {
+                    !(start..=end).contains(&(branch.line_start, branch.column_start))
+                });
+                // the segment at the end describes the code after the function
+                for segment in &mut file.segments {
+                    if (start..end).contains(&(segment.line, segment.col)) {
+                        segment.has_count = false;
+                    }
+                }
+            }
+        }
+    }
+}
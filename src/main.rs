@@ -1,51 +1,267 @@
-use std::path::Path;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use analyzer::{Analyzer, ExtractFunctionsAnalyzer, UncoveredAnalyzer};
-use collector::{check_covmap, get_coverage_report_json, get_coverage_report_json_by_profdata};
-use config::RunningMode;
+use analyzer::{
+    get_binary_coverage, Analyzer, DictionaryAnalyzer, ExtractFunctionsAnalyzer,
+    FunctionReportAnalyzer, LineCoverageAnalyzer, LoopAnalyzer, McdcAnalyzer, SwitchAnalyzer,
+    UncoveredAnalyzer,
+};
+use collector::{
+    check_covmap, get_coverage_report_json, get_coverage_report_json_by_profdata, CoverageResult,
+};
+use config::{AnalysisMode, Config, OutputFormat, RunningMode, RunningOptions, Subcommand};
 use error::GetCovError;
-use llvm_cov_json::CoverageReport;
+use schema::{BinaryMetadata, RunMetadata};
 
 mod analyzer;
+mod changes;
 mod cli;
 mod collector;
 mod config;
+mod demangle;
+mod diff;
 mod error;
+mod export;
+mod filter;
+mod merge;
+mod output;
+mod path_map;
+mod pipeline;
+mod schema;
+mod show;
+mod tui;
 
 fn main() -> Result<(), GetCovError> {
     env_logger::init();
 
     let options = cli::parse_arguments()?;
+
+    if let Subcommand::Schema { functions } = options.subcommand {
+        let schema = schema::output_schema(functions);
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+
+    if let Subcommand::Diff { base, head } = &options.subcommand {
+        if !options.running_options.binary.is_empty() {
+            check_covmap(&options.running_options.binary)?;
+        }
+        let base = diff::load_snapshot(base, &options.running_options, &options.report_filter)?;
+        let head = diff::load_snapshot(head, &options.running_options, &options.report_filter)?;
+        let coverage_diff = diff::diff_snapshots(base, head);
+        for (format, path) in output::selected_outputs(&options.analysis_options) {
+            let mut writer = output::create_writer(path)?;
+            match format {
+                OutputFormat::Text => diff::write_diff(&coverage_diff, &mut writer)?,
+                _ => {
+                    serde_json::to_writer_pretty(&mut writer, &coverage_diff)?;
+                    writeln!(writer)?;
+                }
+            }
+            writer.flush()?;
+        }
+        return Ok(());
+    }
+
     check_covmap(&options.running_options.binary)?;
+    let coverage_result = collect_coverage(
+        &options.running_mode,
+        &options.running_options,
+        options.profdata_file.as_deref(),
+    )?;
 
-    let coverage_result = match options.running_mode {
-        RunningMode::Normal => get_coverage_report_json(&options.running_options)?,
-        RunningMode::Profdata => get_coverage_report_json_by_profdata(
-            &options.running_options,
-            Path::new(&options.profdata_file.unwrap()),
-        )?,
+    let mut profdata_paths = vec![coverage_result.profdata_path.clone()];
+    // the JSON of every binary, which the reports borrow from
+    let mut jsons = vec![(
+        options.running_options.binary.clone(),
+        options
+            .running_options
+            .path_mapper
+            .remap_report_json(&coverage_result.json)?,
+    )];
+    for merged in &options.merged_binaries {
+        check_covmap(&merged.running_options.binary)?;
+        let result = collect_coverage(
+            &merged.running_mode,
+            &merged.running_options,
+            merged.profdata_file.as_deref(),
+        )?;
+        profdata_paths.push(result.profdata_path);
+        jsons.push((
+            merged.running_options.binary.clone(),
+            merged
+                .running_options
+                .path_mapper
+                .remap_report_json(&result.json)?,
+        ));
+    }
+
+    let mut reports = Vec::new();
+    for (binary, json) in &jsons {
+        reports.push((
+            binary.clone(),
+            pipeline::parse_report(json, &options.report_filter)?,
+        ));
+    }
+    let binary_coverage = (reports.len() > 1).then(|| get_binary_coverage(&reports));
+    let attribution = match options.subcommand {
+        Subcommand::Tui => tui::attribute_branches(&reports),
+        _ => None,
     };
+    let (coverage_report, instantiations) =
+        pipeline::merge_parsed_reports(reports.into_iter().map(|(_, report)| report).collect());
+
+    if let Subcommand::Show { target, color } = &options.subcommand {
+        show::write_show(
+            &coverage_report,
+            target,
+            *color,
+            &mut std::io::stdout().lock(),
+        )?;
+        return Ok(());
+    }
 
-    let coverage_report: CoverageReport = serde_json::from_str(&coverage_result.json)?;
-    let mut analyzer: Box<dyn Analyzer> = if options.analysis_options.extract_all_functions {
-        Box::new(ExtractFunctionsAnalyzer::new())
+    if options.subcommand == Subcommand::Tui {
+        return tui::run(
+            &coverage_report,
+            &options.analysis_options.category_filter,
+            attribution,
+        );
+    }
+
+    if let Subcommand::Dict { output } = &options.subcommand {
+        let mut analyzer = DictionaryAnalyzer::new();
+        analyzer.analyze(&coverage_report)?;
+        match output {
+            Some(path) => analyzer.write_dictionary(&mut File::create(path)?)?,
+            None => analyzer.output_text(&mut std::io::stdout())?,
+        }
+        return Ok(());
+    }
+
+    let analysis_options = &options.analysis_options;
+    let outputs = output::selected_outputs(analysis_options);
+
+    if let Some(dir) = &analysis_options.html_dir {
+        export::write_html(&coverage_report, &analysis_options.category_filter, dir)?;
+        eprintln!(
+            "HTML report written to {}",
+            dir.join("index.html").display()
+        );
+    }
+
+    let analyzer = if outputs.iter().any(|(format, _)| format.needs_analysis()) {
+        let mut analyzer: Box<dyn Analyzer> = match &analysis_options.mode {
+            AnalysisMode::Uncovered => Box::new(UncoveredAnalyzer::new(
+                analysis_options.bias_threshold,
+                analysis_options.category_filter.clone(),
+                binary_coverage,
+                instantiations,
+                run_metadata(&options, &profdata_paths)?,
+            )),
+            AnalysisMode::ExtractFunctions => Box::new(ExtractFunctionsAnalyzer::new(
+                instantiations,
+                run_metadata(&options, &profdata_paths)?,
+            )),
+            AnalysisMode::LineCoverage => Box::new(LineCoverageAnalyzer::new()),
+            AnalysisMode::Mcdc => Box::new(McdcAnalyzer::new()),
+            AnalysisMode::Loops => Box::new(LoopAnalyzer::new()),
+            AnalysisMode::Switches => Box::new(SwitchAnalyzer::new()),
+            AnalysisMode::Function(pattern) => Box::new(FunctionReportAnalyzer::new(pattern)?),
+        };
+        analyzer.analyze(&coverage_report)?;
+        Some(analyzer)
     } else {
-        Box::new(UncoveredAnalyzer::new())
+        None
     };
 
-    analyzer.analyze(&coverage_report)?;
-
-    match options.analysis_options.output_format {
-        config::OutputFormat::Json => analyzer.output_json()?,
-        config::OutputFormat::Text => analyzer.output_text(),
-        config::OutputFormat::Hybrid => {
-            analyzer.output_json()?;
-            println!("\n<<<JSON_OUTPUT_END>>>\n");
-            analyzer.output_text();
-            println!("\n<<<TEXT_OUTPUT_END>>>\n");
-            println!("{}", coverage_result.profdata_path.display());
-        }
+    for (format, path) in &outputs {
+        let mut writer = output::create_writer(*path)?;
+        output::write_output(
+            format,
+            &coverage_report,
+            analyzer.as_deref(),
+            analysis_options,
+            &coverage_result.profdata_path,
+            &mut writer,
+        )?;
+        writer.flush()?;
+    }
+
+    if let Some(path) = &analysis_options.manifest {
+        let binaries = std::iter::once(&options.running_options)
+            .chain(options.merged_binaries.iter().map(|m| &m.running_options))
+            .zip(&profdata_paths)
+            .map(|(running_options, profdata)| output::ManifestBinary {
+                binary: running_options.binary.clone(),
+                profdata: profdata.clone(),
+            })
+            .collect();
+        let outputs = outputs
+            .iter()
+            .filter_map(|(format, path)| {
+                Some(output::ManifestOutput {
+                    format: format.name(),
+                    path: (*path)?.to_path_buf(),
+                })
+            })
+            .chain(
+                analysis_options
+                    .html_dir
+                    .iter()
+                    .map(|dir| output::ManifestOutput {
+                        format: "html",
+                        path: dir.join("index.html"),
+                    }),
+            )
+            .collect();
+        output::write_manifest(
+            &output::Manifest {
+                getcov_version: env!("CARGO_PKG_VERSION"),
+                binaries,
+                outputs,
+            },
+            path,
+        )?;
     }
 
     Ok(())
 }
+
+fn collect_coverage(
+    running_mode: &RunningMode,
+    running_options: &RunningOptions,
+    profdata_file: Option<&Path>,
+) -> Result<CoverageResult, GetCovError> {
+    match (running_mode, profdata_file) {
+        (RunningMode::Profdata, Some(profdata_file)) => {
+            get_coverage_report_json_by_profdata(running_options, profdata_file)
+        }
+        _ => get_coverage_report_json(running_options),
+    }
+}
+
+/// Describes the main and the merged binaries for the JSON output. `profdata_paths` are the
+/// profdata files their coverage was read from, in the same order.
+fn run_metadata(options: &Config, profdata_paths: &[PathBuf]) -> Result<RunMetadata, GetCovError> {
+    let binaries = std::iter::once((&options.running_options, &options.profdata_file))
+        .chain(
+            options
+                .merged_binaries
+                .iter()
+                .map(|merged| (&merged.running_options, &merged.profdata_file)),
+        )
+        .zip(profdata_paths)
+        .map(|((running_options, profdata_file), profdata)| {
+            BinaryMetadata::new(
+                &running_options.binary,
+                profdata,
+                profdata_file
+                    .is_none()
+                    .then_some(running_options.args_list.len()),
+            )
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(RunMetadata::new(binaries))
+}
@@ -0,0 +1,323 @@
+use crate::demangle::source_function_name;
+use llvm_cov_json::{
+    Branch, CoverageReport, ExportObject, FileMetrics, FunctionMetrics, Region, RegionKind, Segment,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Merges the reports of several binaries built from the same sources into the first one.
+/// Functions are matched by name and the file they are defined in, and the counts of their
+/// regions and branches at the same position are added up, so a region counts as covered if
+/// any binary covered it. The segments of files shared by several binaries are merged likewise.
+/// The summaries and MC/DC records of shared functions and files are those of the first
+/// binary that has them.
+pub fn merge_reports(reports: Vec<CoverageReport<'_>>) -> Option<CoverageReport<'_>> {
+    let mut reports = reports.into_iter();
+    let mut merged = reports.next()?;
+
+    for report in reports {
+        for export in report.data {
+            merge_export(&mut merged.data[0], export);
+        }
+    }
+    Some(merged)
+}
+
+/// The name of a function and the file it is defined in.
+fn function_key(function: &FunctionMetrics) -> (String, String) {
+    let file = function
+        .regions
+        .first()
+        .map_or("", |region| function.filenames[region.file_id as usize]);
+    (function.name.to_string(), file.to_string())
+}
+
+fn merge_export<'a>(target: &mut ExportObject<'a>, source: ExportObject<'a>) {
+    let mut functions: HashMap<(String, String), usize> = target
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(i, function)| (function_key(function), i))
+        .collect();
+    for function in source.functions {
+        match functions.get(&function_key(&function)) {
+            Some(&i) => merge_function(&mut target.functions[i], function),
+            None => {
+                functions.insert(function_key(&function), target.functions.len());
+                target.functions.push(function);
+            }
+        }
+    }
+
+    let mut files: HashMap<&str, usize> = target
+        .files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (file.filename, i))
+        .collect();
+    for file in source.files {
+        match files.get(file.filename) {
+            Some(&i) => merge_file(&mut target.files[i], file),
+            None => {
+                files.insert(file.filename, target.files.len());
+                target.files.push(file);
+            }
+        }
+    }
+}
+
+fn merge_function<'a>(target: &mut FunctionMetrics<'a>, source: FunctionMetrics<'a>) {
+    target.count += source.count;
+
+    // the file ids of the source are indices into its own list of file names
+    let file_ids: Vec<u64> = source
+        .filenames
+        .iter()
+        .map(
+            |filename| match target.filenames.iter().position(|name| name == filename) {
+                Some(i) => i,
+                None => {
+                    target.filenames.push(filename);
+                    target.filenames.len() - 1
+                }
+            },
+        )
+        .map(|i| i as u64)
+        .collect();
+
+    for mut region in source.regions {
+        region.file_id = file_ids[region.file_id as usize];
+        region.expanded_file_id = file_ids
+            .get(region.expanded_file_id as usize)
+            .copied()
+            .unwrap_or(region.expanded_file_id);
+        match target
+            .regions
+            .iter_mut()
+            .find(|other| same_region(other, &region))
+        {
+            Some(other) => other.execution_count += region.execution_count,
+            None => target.regions.push(region),
+        }
+    }
+
+    for mut branch in source.branches {
+        branch.file_id = file_ids[branch.file_id as usize];
+        branch.expanded_file_id = file_ids
+            .get(branch.expanded_file_id as usize)
+            .copied()
+            .unwrap_or(branch.expanded_file_id);
+        add_branch(&mut target.branches, branch);
+    }
+
+    if target.mcdc_records.is_none() {
+        target.mcdc_records = source.mcdc_records;
+    }
+}
+
+fn merge_file<'a>(target: &mut FileMetrics<'a>, source: FileMetrics<'a>) {
+    for branch in source.branches {
+        add_branch(&mut target.branches, branch);
+    }
+    target.segments = merge_segments(&target.segments, &source.segments);
+    if target.expansions.is_none() {
+        target.expansions = source.expansions;
+    }
+    if target.mcdc_records.is_none() {
+        target.mcdc_records = source.mcdc_records;
+    }
+}
+
+fn same_region(a: &Region, b: &Region) -> bool {
+    (a.line_start, a.column_start, a.line_end, a.column_end)
+        == (b.line_start, b.column_start, b.line_end, b.column_end)
+        && a.file_id == b.file_id
+        && a.expanded_file_id == b.expanded_file_id
+        && a.kind == b.kind
+}
+
+/// Adds the counts of the branch to the branch at the same position, or appends it.
+fn add_branch(branches: &mut Vec<Branch>, branch: Branch) {
+    let same_position = |other: &&mut Branch| {
+        (
+            other.line_start,
+            other.column_start,
+            other.line_end,
+            other.column_end,
+        ) == (
+            branch.line_start,
+            branch.column_start,
+            branch.line_end,
+            branch.column_end,
+        ) && other.file_id == branch.file_id
+            && other.expanded_file_id == branch.expanded_file_id
+    };
+    match branches.iter_mut().find(same_position) {
+        Some(other) => {
+            other.execution_count += branch.execution_count;
+            other.false_execution_count += branch.false_execution_count;
+        }
+        None => branches.push(branch),
+    }
+}
+
+/// Moves `index` past the segments starting at `position` and makes the last of them the
+/// active one. Returns whether any of them enters a region.
+fn advance<'s>(
+    segments: &'s [Segment],
+    index: &mut usize,
+    position: (u64, u64),
+    active: &mut Option<&'s Segment>,
+) -> bool {
+    let mut is_region_entry = false;
+    while let Some(segment) = segments
+        .get(*index)
+        .filter(|segment| (segment.line, segment.col) == position)
+    {
+        is_region_entry |= segment.is_region_entry;
+        *active = Some(segment);
+        *index += 1;
+    }
+    is_region_entry
+}
+
+/// Merges two sorted lists of segments. A segment starts wherever one of the lists has one,
+/// and its count is the sum of the counts in effect there.
+fn merge_segments(a: &[Segment], b: &[Segment]) -> Vec<Segment> {
+    let mut merged = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    // the last segment of each list starting at or before the current position
+    let (mut active_a, mut active_b) = (None, None);
+
+    while let Some(position) = a
+        .get(i)
+        .into_iter()
+        .chain(b.get(j))
+        .map(|segment| (segment.line, segment.col))
+        .min()
+    {
+        let entry_a = advance(a, &mut i, position, &mut active_a);
+        let entry_b = advance(b, &mut j, position, &mut active_b);
+        let active: Vec<&Segment> = [active_a, active_b]
+            .into_iter()
+            .flatten()
+            .filter(|segment| segment.has_count)
+            .collect();
+
+        merged.push(Segment {
+            line: position.0,
+            col: position.1,
+            count: active.iter().map(|segment| segment.count).sum(),
+            has_count: !active.is_empty(),
+            is_region_entry: entry_a || entry_b,
+            // a gap only if no binary has code there
+            is_gap_region: !active.is_empty() && active.iter().all(|segment| segment.is_gap_region),
+        });
+    }
+
+    merged
+}
+
+/// One instantiation of a template or generic function.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Instantiation {
+    pub name: String,
+    pub count: u64,
+    pub covered_branches: u64,
+    pub total_branches: u64,
+}
+
+/// The file, line and column a function starts at, which all its instantiations share.
+pub type SourceLocation = (String, u64, u64);
+
+pub fn source_location(function: &FunctionMetrics) -> Option<SourceLocation> {
+    let region = function
+        .regions
+        .iter()
+        .find(|region| region.kind == RegionKind::Code)?;
+    Some((
+        function.filenames[region.file_id as usize].to_string(),
+        region.line_start,
+        region.column_start,
+    ))
+}
+
+/// The key of the instantiations of one source function: the position of the first region
+/// and the source function name. The first region is in the file the function is used in, so
+/// the functions a macro defines at different places don't share it, and functions defined by
+/// one macro use differ in their names.
+fn instantiation_key(function: &FunctionMetrics) -> Option<(SourceLocation, String)> {
+    let region = function.regions.first()?;
+    Some((
+        (
+            function.filenames[region.file_id as usize].to_string(),
+            region.line_start,
+            region.column_start,
+        ),
+        source_function_name(&function.name),
+    ))
+}
+
+/// Merges the instantiations of template and generic functions, i.e., the functions with the
+/// same source function name that start at the same source location, into one function like
+/// llvm-cov does for its line view.
+/// The merged function is named after the source function, see `source_function_name`.
+/// Returns the instantiations of each merged function by its location.
+pub fn merge_instantiations(
+    coverage_report: &mut CoverageReport,
+) -> HashMap<SourceLocation, Vec<Instantiation>> {
+    let mut instantiations = HashMap::new();
+
+    for export in &mut coverage_report.data {
+        let mut groups: HashMap<(SourceLocation, String), Vec<usize>> = HashMap::new();
+        for (i, function) in export.functions.iter().enumerate() {
+            if let Some(key) = instantiation_key(function) {
+                groups.entry(key).or_default().push(i);
+            }
+        }
+        groups.retain(|_, indices| indices.len() > 1);
+        if groups.is_empty() {
+            continue;
+        }
+
+        let mut functions: Vec<Option<FunctionMetrics>> =
+            export.functions.drain(..).map(Some).collect();
+        for ((_, source_name), indices) in groups {
+            let mut members: Vec<FunctionMetrics> = indices
+                .iter()
+                .filter_map(|&i| functions[i].take())
+                .collect();
+            let group: Vec<Instantiation> = members.iter().map(instantiation).collect();
+
+            let mut merged = members.remove(0);
+            for member in members {
+                merge_function(&mut merged, member);
+            }
+            merged.name = Cow::Owned(source_name);
+            if let Some(location) = source_location(&merged) {
+                instantiations.insert(location, group);
+            }
+            functions[indices[0]] = Some(merged);
+        }
+        export.functions = functions.into_iter().flatten().collect();
+    }
+
+    instantiations
+}
+
+fn instantiation(function: &FunctionMetrics) -> Instantiation {
+    Instantiation {
+        name: function.name.to_string(),
+        count: function.count,
+        covered_branches: function
+            .branches
+            .iter()
+            .map(|branch| {
+                (branch.execution_count > 0) as u64 + (branch.false_execution_count > 0) as u64
+            })
+            .sum(),
+        total_branches: function.branches.len() as u64 * 2,
+    }
+}
@@ -0,0 +1,96 @@
+use crate::analyzer::Analyzer;
+use crate::config::{AnalysisOptions, OutputFormat};
+use crate::error::GetCovError;
+use crate::export;
+use llvm_cov_json::CoverageReport;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Lists what a run read and wrote, so that consumers find its outputs without parsing stdout.
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub getcov_version: &'static str,
+    /// The main binary first, followed by the merged ones.
+    pub binaries: Vec<ManifestBinary>,
+    pub outputs: Vec<ManifestOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestBinary {
+    pub binary: String,
+    pub profdata: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestOutput {
+    pub format: &'static str,
+    pub path: PathBuf,
+}
+
+/// Opens the file to write an output to, or stdout if no file is given.
+pub fn create_writer(path: Option<&Path>) -> io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout()),
+    })
+}
+
+/// The outputs to write, as formats and the files to write them to, `None` for stdout.
+/// The output in `--format` goes to stdout unless `-o` is given, `--html` replaces it.
+pub fn selected_outputs(analysis_options: &AnalysisOptions) -> Vec<(&OutputFormat, Option<&Path>)> {
+    let mut outputs = Vec::new();
+    if analysis_options.html_dir.is_none() {
+        outputs.push((
+            &analysis_options.output_format,
+            analysis_options.output_file.as_deref(),
+        ));
+    }
+    outputs.extend(
+        analysis_options
+            .emits
+            .iter()
+            .map(|(format, path)| (format, Some(path.as_path()))),
+    );
+    outputs
+}
+
+/// Writes one output of the run. The analyzer has to be given if the format needs it, see
+/// `OutputFormat::needs_analysis`.
+pub fn write_output(
+    format: &OutputFormat,
+    coverage_report: &CoverageReport,
+    analyzer: Option<&dyn Analyzer>,
+    analysis_options: &AnalysisOptions,
+    profdata_path: &Path,
+    writer: &mut dyn Write,
+) -> Result<(), GetCovError> {
+    let analyzer = || analyzer.expect("The analysis runs for all formats that need it");
+
+    match format {
+        OutputFormat::Json => analyzer().output_json(writer)?,
+        OutputFormat::Text => analyzer().output_text(writer)?,
+        OutputFormat::Hybrid => {
+            analyzer().output_json(writer)?;
+            writeln!(writer, "\n<<<JSON_OUTPUT_END>>>\n")?;
+            analyzer().output_text(writer)?;
+            writeln!(writer, "\n<<<TEXT_OUTPUT_END>>>\n")?;
+            writeln!(writer, "{}", profdata_path.display())?;
+        }
+        OutputFormat::Sarif => analyzer().output_sarif(writer)?,
+        OutputFormat::Markdown => analyzer().output_markdown(&analysis_options.markdown, writer)?,
+        OutputFormat::Prompt => analyzer().output_prompt(&analysis_options.prompt, writer)?,
+        OutputFormat::Lcov => export::write_lcov(coverage_report, writer)?,
+        OutputFormat::Cobertura => export::write_cobertura(coverage_report, writer)?,
+    }
+    Ok(())
+}
+
+pub fn write_manifest(manifest: &Manifest, path: &Path) -> Result<(), GetCovError> {
+    let mut writer = create_writer(Some(path))?;
+    serde_json::to_writer_pretty(&mut writer, manifest)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::error::GetCovError;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Maps the source paths recorded at compile time to paths on this machine, e.g. for
+/// binaries built in a container. The first `from=to` rule whose `from` is a prefix of
+/// the path replaces that prefix; paths that are still relative are resolved against
+/// the source root.
+#[derive(Debug, Clone, Default)]
+pub struct PathMapper {
+    pub rules: Vec<(String, String)>,
+    pub source_root: Option<PathBuf>,
+}
+
+impl PathMapper {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.source_root.is_none()
+    }
+
+    pub fn map(&self, path: &str) -> String {
+        let mut mapped = path.to_string();
+
+        for (from, to) in &self.rules {
+            let from = from.trim_end_matches('/');
+            if let Some(rest) = path.strip_prefix(from) {
+                // only replace whole path components
+                if rest.is_empty() || rest.starts_with('/') || from.is_empty() {
+                    mapped = format!("{}{}", to.trim_end_matches('/'), rest);
+                    break;
+                }
+            }
+        }
+
+        match &self.source_root {
+            Some(root) if Path::new(&mapped).is_relative() => {
+                root.join(&mapped).to_string_lossy().into_owned()
+            }
+            _ => mapped,
+        }
+    }
+
+    /// Rewrites every `filename` and `filenames` entry of an llvm-cov JSON export,
+    /// so that all analyzers and snippets see the mapped paths.
+    pub fn remap_report_json(&self, json: &str) -> Result<String, GetCovError> {
+        if self.is_empty() {
+            return Ok(json.to_string());
+        }
+
+        let mut report: Value = serde_json::from_str(json)?;
+        self.remap_value(&mut report);
+        Ok(serde_json::to_string(&report)?)
+    }
+
+    fn remap_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    match (key.as_str(), value) {
+                        ("filename", Value::String(path)) => *path = self.map(path),
+                        ("filenames", Value::Array(paths)) => {
+                            for path in paths {
+                                if let Value::String(path) = path {
+                                    *path = self.map(path);
+                                }
+                            }
+                        }
+                        (_, value) => self.remap_value(value),
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.remap_value(value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
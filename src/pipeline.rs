@@ -0,0 +1,33 @@
+use crate::demangle::demangle_report;
+use crate::error::GetCovError;
+use crate::filter::ReportFilter;
+use crate::merge::{merge_instantiations, merge_reports, Instantiation, SourceLocation};
+use llvm_cov_json::CoverageReport;
+use std::collections::HashMap;
+
+/// Parses the llvm-cov export of one binary, with its paths already mapped, demangles the
+/// function names and drops the files and functions the filter doesn't select.
+pub fn parse_report<'a>(
+    json: &'a str,
+    report_filter: &ReportFilter,
+) -> Result<CoverageReport<'a>, GetCovError> {
+    let mut report: CoverageReport = serde_json::from_str(json)?;
+    demangle_report(&mut report);
+    report_filter.apply(&mut report);
+    Ok(report)
+}
+
+/// Merges the parsed reports of the binaries into the first one, then merges the
+/// instantiations of every function. Returns the instantiations of each merged function
+/// by its location, see `merge_instantiations`.
+pub fn merge_parsed_reports(
+    reports: Vec<CoverageReport<'_>>,
+) -> (
+    CoverageReport<'_>,
+    HashMap<SourceLocation, Vec<Instantiation>>,
+) {
+    let mut coverage_report =
+        merge_reports(reports).expect("The report of the main binary is always present");
+    let instantiations = merge_instantiations(&mut coverage_report);
+    (coverage_report, instantiations)
+}
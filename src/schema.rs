@@ -0,0 +1,90 @@
+use crate::analyzer::{FunctionList, UncoveredOutput};
+use crate::error::GetCovError;
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The version of the JSON outputs of the uncovered analysis and of `--all`. It is increased
+/// whenever a field is removed or renamed or changes its meaning, but not for new fields.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What a JSON output was generated from, to tell outputs apart and to reproduce them.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RunMetadata {
+    pub getcov_version: String,
+    /// The version reported by `llvm-cov --version`, e.g. `18.1.3`, if it could be determined.
+    pub llvm_version: Option<String>,
+    /// When the report was generated, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The main binary first, followed by the merged ones.
+    pub binaries: Vec<BinaryMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BinaryMetadata {
+    pub binary: String,
+    /// The SHA-256 hash of the binary, in lowercase hex.
+    pub sha256: String,
+    pub profdata: String,
+    /// The number of inputs the binary was run with, `None` if an existing profdata file was
+    /// read instead.
+    pub seed_count: Option<usize>,
+}
+
+impl BinaryMetadata {
+    pub fn new(
+        binary: &str,
+        profdata: &Path,
+        seed_count: Option<usize>,
+    ) -> Result<Self, GetCovError> {
+        let mut hasher = Sha256::new();
+        io::copy(&mut File::open(binary)?, &mut hasher)?;
+        Ok(Self {
+            binary: binary.to_string(),
+            sha256: format!("{:x}", hasher.finalize()),
+            profdata: profdata.display().to_string(),
+            seed_count,
+        })
+    }
+}
+
+impl RunMetadata {
+    pub fn new(binaries: Vec<BinaryMetadata>) -> Self {
+        Self {
+            getcov_version: env!("CARGO_PKG_VERSION").to_string(),
+            llvm_version: llvm_version(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            binaries,
+        }
+    }
+}
+
+/// Reads the LLVM version from the `LLVM version X.Y.Z` line of `llvm-cov --version`.
+fn llvm_version() -> Option<String> {
+    let output = Command::new("llvm-cov").arg("--version").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| Some(line.split("LLVM version ").nth(1)?.trim().to_string()))
+}
+
+/// Generates the JSON Schema of the output of the uncovered analysis, or of `--all` if
+/// `functions` is set. The schema describes the output as written, so fields that are
+/// `null` when unset are required as well.
+pub fn output_schema(functions: bool) -> Schema {
+    let generator = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator();
+    if functions {
+        generator.into_root_schema_for::<FunctionList>()
+    } else {
+        generator.into_root_schema_for::<UncoveredOutput>()
+    }
+}
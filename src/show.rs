@@ -0,0 +1,217 @@
+use crate::error::GetCovError;
+use crate::merge::source_location;
+use llvm_cov_json::{Branch, CoverageReport, FileMetrics, FunctionMetrics, RegionKind};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Write};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+
+/// A part of a source file to print, with the branches to mark in it.
+struct View<'a> {
+    title: String,
+    file: &'a FileMetrics<'a>,
+    start_line: u64,
+    end_line: u64,
+    branches: Vec<&'a Branch>,
+}
+
+/// Prints the source of the files matching `target`, or else of the functions matching it,
+/// with the execution count of every line and, under each branch, how often it was true and
+/// false. A file matches if `target` is its path or the end of it after a `/`, a function if
+/// `target` is its name or a regex matching the whole name. With `color`, ANSI escapes mark
+/// the uncovered lines and the one-sided branches.
+pub fn write_show(
+    coverage_report: &CoverageReport,
+    target: &str,
+    color: bool,
+    writer: &mut dyn Write,
+) -> Result<(), GetCovError> {
+    let files: HashMap<&str, &FileMetrics> = coverage_report.data[0]
+        .files
+        .iter()
+        .map(|file| (file.filename, file))
+        .collect();
+
+    let mut views: Vec<View> = coverage_report.data[0]
+        .files
+        .iter()
+        .filter(|file| {
+            file.filename == target
+                || file
+                    .filename
+                    .strip_suffix(target)
+                    .is_some_and(|prefix| prefix.ends_with('/'))
+        })
+        .map(|file| View {
+            title: file.filename.to_string(),
+            file,
+            start_line: 1,
+            end_line: u64::MAX,
+            branches: file.branches.iter().collect(),
+        })
+        .collect();
+    views.sort_by(|a, b| a.title.cmp(&b.title));
+
+    if views.is_empty() {
+        let name_re = Regex::new(&format!("^(?:{})$", target)).map_err(|e| {
+            GetCovError::ArgParse(format!("Invalid function pattern '{}': {}", target, e))
+        })?;
+        let mut functions: Vec<&FunctionMetrics> = coverage_report.data[0]
+            .functions
+            .iter()
+            .filter(|function| function.name == target || name_re.is_match(&function.name))
+            .collect();
+        functions.sort_by_key(|function| source_location(function));
+        views = functions
+            .into_iter()
+            .filter_map(|function| function_view(function, &files))
+            .collect();
+    }
+
+    if views.is_empty() {
+        return Err(GetCovError::Coverage(format!(
+            "No file or function in the report matches '{}'",
+            target
+        )));
+    }
+
+    for (i, view) in views.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+        write_view(view, color, writer)?;
+    }
+    Ok(())
+}
+
+/// The lines of the function in its own file, taken from its first code region.
+fn function_view<'a>(
+    function: &'a FunctionMetrics<'a>,
+    files: &HashMap<&str, &'a FileMetrics<'a>>,
+) -> Option<View<'a>> {
+    let region = function
+        .regions
+        .iter()
+        .find(|region| region.kind == RegionKind::Code)?;
+    let file_path = function.filenames[region.file_id as usize];
+    Some(View {
+        title: format!("{} ({}:{})", function.name, file_path, region.line_start),
+        file: files.get(file_path)?,
+        start_line: region.line_start,
+        end_line: region.line_end,
+        branches: function
+            .branches
+            .iter()
+            .filter(|branch| function.filenames[branch.file_id as usize] == file_path)
+            .collect(),
+    })
+}
+
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+fn write_view(view: &View, color: bool, writer: &mut dyn Write) -> io::Result<()> {
+    writeln!(writer, "{}", paint(&view.title, BOLD, color))?;
+
+    let source = match fs::read_to_string(view.file.filename) {
+        Ok(source) => source,
+        Err(e) => return writeln!(writer, "  The source file could not be read: {}", e),
+    };
+    let lines: Vec<&str> = source.lines().collect();
+    let end_line = view.end_line.min(lines.len() as u64);
+
+    let counts: HashMap<u64, u64> = view
+        .file
+        .line_coverage()
+        .into_iter()
+        .filter(|line| line.mapped)
+        .map(|line| (line.line, line.execution_count))
+        .collect();
+    let mut branches: BTreeMap<u64, Vec<&Branch>> = BTreeMap::new();
+    for branch in &view.branches {
+        branches.entry(branch.line_start).or_default().push(branch);
+    }
+
+    let number_width = end_line.to_string().len();
+    let count_width = (view.start_line..=end_line)
+        .filter_map(|line| counts.get(&line))
+        .map(|count| count.to_string().len())
+        .max()
+        .unwrap_or(1)
+        .max(5);
+
+    for line in view.start_line..=end_line {
+        let text = lines[line as usize - 1];
+        let count = counts.get(&line);
+        let count_text = format!(
+            "{:>width$}",
+            count.map(u64::to_string).unwrap_or_default(),
+            width = count_width
+        );
+        let (count_text, text) = match count {
+            Some(0) => (paint(&count_text, RED, color), paint(text, RED, color)),
+            Some(_) => (paint(&count_text, GREEN, color), text.to_string()),
+            None => (count_text, text.to_string()),
+        };
+        writeln!(
+            writer,
+            "{:>number_width$}|{}|{}",
+            line,
+            count_text,
+            text,
+            number_width = number_width
+        )?;
+
+        let Some(line_branches) = branches.get_mut(&line) else {
+            continue;
+        };
+        line_branches.sort_by_key(|branch| branch.column_start);
+        for branch in line_branches.iter() {
+            // keep the tabs of the line, so that the marker lines up with the branch
+            let indent: String = text_prefix(lines[line as usize - 1], branch.column_start)
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let marker = format!(
+                "^ True: {}, False: {}",
+                branch.execution_count, branch.false_execution_count
+            );
+            let style = match (branch.execution_count > 0, branch.false_execution_count > 0) {
+                (true, true) => GREEN,
+                (false, false) => RED,
+                _ => YELLOW,
+            };
+            writeln!(
+                writer,
+                "{:number_width$}|{:count_width$}|{}{}",
+                "",
+                "",
+                indent,
+                paint(&marker, style, color),
+                number_width = number_width,
+                count_width = count_width
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the text of the line in front of a 1-based column, clamped to the line.
+fn text_prefix(line: &str, column: u64) -> &str {
+    let mut end = (column.saturating_sub(1) as usize).min(line.len());
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    &line[..end]
+}
@@ -0,0 +1,593 @@
+use crate::analyzer::PartiallyCoveredFunction;
+use crate::export::evaluations;
+use crate::merge::source_location;
+use llvm_cov_json::CoverageReport;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+/// The file, line and column of a branch.
+pub type BranchLocation = (String, u64, u64);
+
+/// The summed true and false counts of a branch in each of the merged binaries.
+pub type Attribution = HashMap<BranchLocation, Vec<(String, u64, u64)>>;
+
+/// A file or function in the list, with the size of its uncovered part.
+pub struct Entry {
+    pub name: String,
+    pub file_path: String,
+    /// The line to open the source at.
+    pub line: u64,
+    pub uncovered_lines: usize,
+    pub total_lines: usize,
+    pub predicates: usize,
+    pub regions: usize,
+}
+
+impl Entry {
+    fn is_covered(&self) -> bool {
+        self.uncovered_lines == 0 && self.predicates == 0 && self.regions == 0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HighlightKind {
+    UncoveredRegion,
+    OneSided,
+}
+
+/// A range of the open file to mark.
+pub struct Highlight {
+    pub start: (u64, u64),
+    pub end: (u64, u64),
+    pub kind: HighlightKind,
+    /// Describes the one-sided predicate, e.g., `always false (3 evaluations)`.
+    pub label: String,
+}
+
+/// An open source file, with its line counts and the uncovered parts to mark.
+pub struct SourceView {
+    pub file_path: String,
+    pub lines: Vec<String>,
+    pub counts: BTreeMap<u64, u64>,
+    /// Regions first, so that the predicates in them are shown on top.
+    pub highlights: Vec<Highlight>,
+    /// The 1-based line the cursor is on.
+    pub cursor: u64,
+    /// The first line shown.
+    pub scroll: u64,
+}
+
+impl SourceView {
+    /// The one-sided predicates on the cursor line.
+    pub fn predicates_at_cursor(&self) -> impl Iterator<Item = &Highlight> {
+        self.highlights.iter().filter(move |highlight| {
+            highlight.kind == HighlightKind::OneSided && highlight.start.0 == self.cursor
+        })
+    }
+
+    fn predicate_lines(&self) -> Vec<u64> {
+        let mut lines: Vec<u64> = self
+            .highlights
+            .iter()
+            .filter(|highlight| highlight.kind == HighlightKind::OneSided)
+            .map(|highlight| highlight.start.0)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    }
+
+    fn move_cursor(&mut self, line: i64) {
+        self.cursor = line.clamp(1, self.lines.len().max(1) as i64) as u64;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ListKind {
+    Files,
+    Functions,
+}
+
+/// The state of the browser.
+pub struct App {
+    pub files: Vec<Entry>,
+    pub functions: Vec<Entry>,
+    uncovered_functions: Vec<PartiallyCoveredFunction>,
+    /// The execution counts of the instrumented lines of every file.
+    line_counts: HashMap<String, BTreeMap<u64, u64>>,
+    pub attribution: Option<Attribution>,
+    pub list: ListKind,
+    /// Only entries whose name contains the filter, ignoring case, are listed.
+    pub filter: String,
+    pub editing_filter: bool,
+    /// List fully covered files and functions as well.
+    pub show_covered: bool,
+    /// The selected row of the filtered list.
+    pub selected: usize,
+    pub source: Option<SourceView>,
+    pub message: Option<String>,
+}
+
+impl App {
+    pub fn new(
+        coverage_report: &CoverageReport,
+        uncovered_functions: Vec<PartiallyCoveredFunction>,
+        attribution: Option<Attribution>,
+    ) -> Self {
+        let line_counts: HashMap<String, BTreeMap<u64, u64>> = coverage_report.data[0]
+            .files
+            .iter()
+            .map(|file| {
+                let counts = file
+                    .line_coverage()
+                    .into_iter()
+                    .filter(|line| line.mapped)
+                    .map(|line| (line.line, line.execution_count))
+                    .collect();
+                (file.filename.to_string(), counts)
+            })
+            .collect();
+        let mut app = Self {
+            files: file_entries(&line_counts, &uncovered_functions),
+            functions: function_entries(coverage_report, &line_counts, &uncovered_functions),
+            uncovered_functions,
+            line_counts,
+            attribution,
+            list: ListKind::Files,
+            filter: String::new(),
+            editing_filter: false,
+            show_covered: false,
+            selected: 0,
+            source: None,
+            message: None,
+        };
+        sort_entries(&mut app.files);
+        sort_entries(&mut app.functions);
+        app
+    }
+
+    /// The entries of the current list that pass the filters.
+    pub fn visible_entries(&self) -> Vec<&Entry> {
+        let filter = self.filter.to_lowercase();
+        let entries = match self.list {
+            ListKind::Files => &self.files,
+            ListKind::Functions => &self.functions,
+        };
+        entries
+            .iter()
+            .filter(|entry| self.show_covered || !entry.is_covered())
+            .filter(|entry| entry.name.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    pub fn switch_list(&mut self) {
+        self.list = match self.list {
+            ListKind::Files => ListKind::Functions,
+            ListKind::Functions => ListKind::Files,
+        };
+        self.selected = 0;
+    }
+
+    pub fn toggle_covered(&mut self) {
+        self.show_covered = !self.show_covered;
+        self.clamp_selection();
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.clamp_selection();
+    }
+
+    pub fn pop_filter(&mut self) {
+        self.filter.pop();
+        self.clamp_selection();
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.clamp_selection();
+    }
+
+    pub fn move_selection(&mut self, delta: i64) {
+        let count = self.visible_entries().len() as i64;
+        self.selected = (self.selected as i64 + delta).clamp(0, (count - 1).max(0)) as usize;
+    }
+
+    fn clamp_selection(&mut self) {
+        self.move_selection(0);
+    }
+
+    /// Opens the source of the selected entry at its first line.
+    pub fn open_selected(&mut self) {
+        let Some((file_path, line)) = self
+            .visible_entries()
+            .get(self.selected)
+            .map(|entry| (entry.file_path.clone(), entry.line))
+        else {
+            return;
+        };
+        match fs::read_to_string(&file_path) {
+            Ok(source) => {
+                let mut view = self.source_view(file_path, &source);
+                view.move_cursor(line as i64);
+                view.scroll = view.cursor.saturating_sub(1).max(1);
+                self.source = Some(view);
+            }
+            Err(e) => self.message = Some(format!("Cannot read {}: {}", file_path, e)),
+        }
+    }
+
+    fn source_view(&self, file_path: String, source: &str) -> SourceView {
+        let mut highlights = Vec::new();
+        for function in &self.uncovered_functions {
+            for region in &function.uncovered_regions {
+                if region.file_path == file_path {
+                    highlights.push(Highlight {
+                        start: (region.start_line, region.start_column),
+                        end: (region.end_line, region.end_column),
+                        kind: HighlightKind::UncoveredRegion,
+                        label: String::new(),
+                    });
+                }
+            }
+        }
+        for function in &self.uncovered_functions {
+            for predicate in &function.partially_covered_predicates {
+                if predicate.file_path == file_path {
+                    highlights.push(Highlight {
+                        start: (predicate.start_line, predicate.start_column),
+                        end: (predicate.end_line, predicate.end_column),
+                        kind: HighlightKind::OneSided,
+                        label: if predicate.false_count == 0 {
+                            format!("always true ({})", evaluations(predicate.true_count))
+                        } else {
+                            format!("always false ({})", evaluations(predicate.false_count))
+                        },
+                    });
+                }
+            }
+        }
+
+        SourceView {
+            counts: self
+                .line_counts
+                .get(&file_path)
+                .cloned()
+                .unwrap_or_default(),
+            lines: source.lines().map(str::to_string).collect(),
+            highlights,
+            cursor: 1,
+            scroll: 1,
+            file_path,
+        }
+    }
+
+    pub fn close_source(&mut self) {
+        self.source = None;
+    }
+
+    pub fn move_cursor(&mut self, delta: i64) {
+        if let Some(view) = &mut self.source {
+            view.move_cursor(view.cursor as i64 + delta);
+        }
+    }
+
+    /// Moves the cursor to the next one-sided predicate, or the previous one if `forward` is
+    /// not set, wrapping around at the end of the file.
+    pub fn jump_to_predicate(&mut self, forward: bool) {
+        let Some(view) = &mut self.source else {
+            return;
+        };
+        let lines = view.predicate_lines();
+        let target = if forward {
+            lines
+                .iter()
+                .find(|&&line| line > view.cursor)
+                .or(lines.first())
+        } else {
+            lines
+                .iter()
+                .rev()
+                .find(|&&line| line < view.cursor)
+                .or(lines.last())
+        };
+        match target {
+            Some(&line) => view.move_cursor(line as i64),
+            None => self.message = Some("No one-sided predicates in this file".to_string()),
+        }
+    }
+}
+
+/// Sorts the entries by their uncovered lines, predicates and regions, most first, then by name.
+fn sort_entries(entries: &mut [Entry]) {
+    entries.sort_by(|a, b| {
+        (b.uncovered_lines, b.predicates, b.regions, &a.name).cmp(&(
+            a.uncovered_lines,
+            a.predicates,
+            a.regions,
+            &b.name,
+        ))
+    });
+}
+
+/// Counts the uncovered and the instrumented lines between `start_line` and `end_line`.
+fn count_lines(
+    counts: Option<&BTreeMap<u64, u64>>,
+    start_line: u64,
+    end_line: u64,
+) -> (usize, usize) {
+    counts.map_or((0, 0), |counts| {
+        counts
+            .range(start_line..=end_line)
+            .fold((0, 0), |(uncovered, total), (_, count)| {
+                (uncovered + (*count == 0) as usize, total + 1)
+            })
+    })
+}
+
+fn file_entries(
+    line_counts: &HashMap<String, BTreeMap<u64, u64>>,
+    uncovered_functions: &[PartiallyCoveredFunction],
+) -> Vec<Entry> {
+    line_counts
+        .iter()
+        .map(|(file_path, counts)| {
+            let (uncovered_lines, total_lines) = count_lines(Some(counts), 1, u64::MAX);
+            Entry {
+                name: file_path.clone(),
+                file_path: file_path.clone(),
+                line: 1,
+                uncovered_lines,
+                total_lines,
+                predicates: uncovered_functions
+                    .iter()
+                    .flat_map(|function| &function.partially_covered_predicates)
+                    .filter(|predicate| &predicate.file_path == file_path)
+                    .count(),
+                regions: uncovered_functions
+                    .iter()
+                    .flat_map(|function| &function.uncovered_regions)
+                    .filter(|region| &region.file_path == file_path)
+                    .count(),
+            }
+        })
+        .collect()
+}
+
+fn function_entries(
+    coverage_report: &CoverageReport,
+    line_counts: &HashMap<String, BTreeMap<u64, u64>>,
+    uncovered_functions: &[PartiallyCoveredFunction],
+) -> Vec<Entry> {
+    let partial: HashMap<(&str, u64), &PartiallyCoveredFunction> = uncovered_functions
+        .iter()
+        .filter_map(|function| {
+            let whole_function = function.whole_function.as_ref()?;
+            Some((
+                (whole_function.file_path.as_str(), whole_function.start_line),
+                function,
+            ))
+        })
+        .collect();
+
+    coverage_report.data[0]
+        .functions
+        .iter()
+        .filter_map(|function| {
+            let (file_path, start_line, _) = source_location(function)?;
+            let end_line = function
+                .regions
+                .iter()
+                .find(|region| region.line_start == start_line)
+                .map_or(start_line, |region| region.line_end);
+            let (uncovered_lines, total_lines) =
+                count_lines(line_counts.get(&file_path), start_line, end_line);
+            let uncovered = partial.get(&(file_path.as_str(), start_line));
+            Some(Entry {
+                name: function.name.to_string(),
+                predicates: uncovered
+                    .map_or(0, |function| function.partially_covered_predicates.len()),
+                regions: uncovered.map_or(0, |function| function.uncovered_regions.len()),
+                file_path,
+                line: start_line,
+                uncovered_lines,
+                total_lines,
+            })
+        })
+        .collect()
+}
+
+/// Collects the counts of every branch in each binary, if several binaries were merged.
+/// The counts of the instantiations of a function in one binary are added up.
+pub fn attribute_branches(reports: &[(String, CoverageReport)]) -> Option<Attribution> {
+    if reports.len() < 2 {
+        return None;
+    }
+    let mut attribution = Attribution::new();
+    for (binary, report) in reports {
+        for function in &report.data[0].functions {
+            for branch in &function.branches {
+                let counts = attribution
+                    .entry((
+                        function.filenames[branch.file_id as usize].to_string(),
+                        branch.line_start,
+                        branch.column_start,
+                    ))
+                    .or_default();
+                // the binaries come one after the other, so only the last entry can be this one
+                match counts.last_mut() {
+                    Some((name, true_count, false_count)) if name == binary => {
+                        *true_count += branch.execution_count;
+                        *false_count += branch.false_execution_count;
+                    }
+                    _ => counts.push((
+                        binary.clone(),
+                        branch.execution_count,
+                        branch.false_execution_count,
+                    )),
+                }
+            }
+        }
+    }
+    Some(attribution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, uncovered_lines: usize, predicates: usize, regions: usize) -> Entry {
+        Entry {
+            name: name.to_string(),
+            file_path: format!("{}.c", name),
+            line: 1,
+            uncovered_lines,
+            total_lines: 10,
+            predicates,
+            regions,
+        }
+    }
+
+    fn app(files: Vec<Entry>) -> App {
+        App {
+            files,
+            functions: Vec::new(),
+            uncovered_functions: Vec::new(),
+            line_counts: HashMap::new(),
+            attribution: None,
+            list: ListKind::Files,
+            filter: String::new(),
+            editing_filter: false,
+            show_covered: false,
+            selected: 0,
+            source: None,
+            message: None,
+        }
+    }
+
+    fn predicate(line: u64) -> Highlight {
+        Highlight {
+            start: (line, 5),
+            end: (line, 10),
+            kind: HighlightKind::OneSided,
+            label: "always true (1 evaluation)".to_string(),
+        }
+    }
+
+    fn source_view(highlights: Vec<Highlight>) -> SourceView {
+        SourceView {
+            file_path: "main.c".to_string(),
+            lines: vec![String::new(); 30],
+            counts: BTreeMap::new(),
+            highlights,
+            cursor: 1,
+            scroll: 1,
+        }
+    }
+
+    fn names(app: &App) -> Vec<&str> {
+        app.visible_entries()
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let mut entries = vec![
+            entry("b", 2, 0, 1),
+            entry("a", 2, 0, 1),
+            entry("c", 2, 3, 0),
+            entry("d", 5, 0, 0),
+            entry("e", 2, 0, 2),
+        ];
+        sort_entries(&mut entries);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["d", "c", "e", "a", "b"]);
+    }
+
+    #[test]
+    fn test_count_lines() {
+        let counts = BTreeMap::from([(2, 0), (3, 4), (5, 0), (9, 1)]);
+        assert_eq!(count_lines(Some(&counts), 3, 5), (1, 2));
+        assert_eq!(count_lines(Some(&counts), 1, u64::MAX), (2, 4));
+        assert_eq!(count_lines(Some(&counts), 6, 8), (0, 0));
+        assert_eq!(count_lines(None, 1, 10), (0, 0));
+    }
+
+    #[test]
+    fn test_live_filter() {
+        let mut app = app(vec![
+            entry("parser", 3, 0, 0),
+            entry("Lexer", 2, 0, 0),
+            entry("print", 1, 0, 0),
+            entry("covered", 0, 0, 0),
+        ]);
+        assert_eq!(names(&app), ["parser", "Lexer", "print"]);
+
+        app.move_selection(2);
+        assert_eq!(app.selected, 2);
+        app.push_filter('p');
+        assert_eq!(names(&app), ["parser", "print"]);
+        assert_eq!(app.selected, 1);
+
+        app.push_filter('a');
+        assert_eq!(names(&app), ["parser"]);
+        assert_eq!(app.selected, 0);
+
+        app.pop_filter();
+        assert_eq!(names(&app), ["parser", "print"]);
+
+        app.clear_filter();
+        for c in "LEX".chars() {
+            app.push_filter(c);
+        }
+        assert_eq!(names(&app), ["Lexer"]);
+
+        app.clear_filter();
+        app.push_filter('v');
+        assert!(names(&app).is_empty());
+        assert_eq!(app.selected, 0);
+        app.toggle_covered();
+        assert_eq!(names(&app), ["covered"]);
+    }
+
+    #[test]
+    fn test_jump_to_predicate() {
+        let mut app = app(Vec::new());
+        app.source = Some(source_view(vec![
+            predicate(12),
+            predicate(4),
+            predicate(12),
+            Highlight {
+                start: (8, 1),
+                end: (9, 2),
+                kind: HighlightKind::UncoveredRegion,
+                label: String::new(),
+            },
+            predicate(20),
+        ]));
+        let cursor = |app: &App| app.source.as_ref().unwrap().cursor;
+
+        app.jump_to_predicate(true);
+        assert_eq!(cursor(&app), 4);
+        app.jump_to_predicate(true);
+        assert_eq!(cursor(&app), 12);
+        app.jump_to_predicate(true);
+        assert_eq!(cursor(&app), 20);
+        app.jump_to_predicate(true);
+        assert_eq!(cursor(&app), 4);
+
+        app.jump_to_predicate(false);
+        assert_eq!(cursor(&app), 20);
+        app.move_cursor(-5);
+        app.jump_to_predicate(false);
+        assert_eq!(cursor(&app), 12);
+        assert!(app.message.is_none());
+
+        app.source = Some(source_view(Vec::new()));
+        app.jump_to_predicate(true);
+        assert_eq!(cursor(&app), 1);
+        assert!(app.message.is_some());
+    }
+}
@@ -0,0 +1,106 @@
+mod app;
+mod ui;
+
+pub use app::attribute_branches;
+
+use crate::analyzer::{get_uncovered, LineCoverageMap, RegionCategoryFilter};
+use crate::error::GetCovError;
+use app::{App, Attribution};
+use llvm_cov_json::CoverageReport;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::DefaultTerminal;
+use std::io;
+
+/// Lines moved by Page Up and Page Down.
+const PAGE: i64 = 20;
+
+/// Browses the files and functions of the report in the terminal, sorted by the size of their
+/// uncovered part, and their source with the uncovered regions and one-sided predicates
+/// marked. `attribution` has the branch counts of each binary, if several were merged.
+pub fn run(
+    coverage_report: &CoverageReport,
+    category_filter: &RegionCategoryFilter,
+    attribution: Option<Attribution>,
+) -> Result<(), GetCovError> {
+    let line_coverage = LineCoverageMap::new(coverage_report);
+    let uncovered_functions = get_uncovered(coverage_report, &line_coverage, category_filter);
+    let mut app = App::new(coverage_report, uncovered_functions, attribution);
+
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::try_restore()?;
+    result?;
+    Ok(())
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if !handle_key(app, key) {
+            return Ok(());
+        }
+    }
+}
+
+/// Updates the state for a key press. Returns `false` to quit.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return false;
+    }
+
+    if app.editing_filter {
+        match key.code {
+            KeyCode::Enter => app.editing_filter = false,
+            KeyCode::Esc => {
+                app.editing_filter = false;
+                app.clear_filter();
+            }
+            KeyCode::Backspace => app.pop_filter(),
+            KeyCode::Char(c) => app.push_filter(c),
+            _ => {}
+        }
+        return true;
+    }
+
+    if app.source.is_some() {
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Esc | KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => {
+                app.close_source()
+            }
+            KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1),
+            KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => app.move_cursor(PAGE),
+            KeyCode::PageUp => app.move_cursor(-PAGE),
+            KeyCode::Home | KeyCode::Char('g') => app.move_cursor(i64::MIN / 2),
+            KeyCode::End | KeyCode::Char('G') => app.move_cursor(i64::MAX / 2),
+            KeyCode::Char('n') => app.jump_to_predicate(true),
+            KeyCode::Char('N') => app.jump_to_predicate(false),
+            _ => {}
+        }
+        return true;
+    }
+
+    match key.code {
+        KeyCode::Esc if !app.filter.is_empty() => app.clear_filter(),
+        KeyCode::Char('q') | KeyCode::Esc => return false,
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => app.open_selected(),
+        KeyCode::Tab => app.switch_list(),
+        KeyCode::Char('/') => app.editing_filter = true,
+        KeyCode::Char('a') => app.toggle_covered(),
+        KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+        KeyCode::PageDown => app.move_selection(PAGE),
+        KeyCode::PageUp => app.move_selection(-PAGE),
+        KeyCode::Home | KeyCode::Char('g') => app.move_selection(i64::MIN / 2),
+        KeyCode::End | KeyCode::Char('G') => app.move_selection(i64::MAX / 2),
+        _ => {}
+    }
+    true
+}
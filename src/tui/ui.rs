@@ -0,0 +1,231 @@
+use super::app::{App, Entry, HighlightKind, ListKind, SourceView};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+const KEYS_LIST: &str = "Enter open  Tab files/functions  / filter  a covered  j/k move  q quit";
+const KEYS_SOURCE: &str = "n/N next/previous predicate  j/k move  PgUp/PgDn page  Esc back  q quit";
+const KEYS_FILTER: &str = "type to filter  Enter keep  Esc clear";
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [body, status, keys] = Layout::vertical([
+        Constraint::Min(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let status_text = match (&app.source, app.message.take()) {
+        (_, Some(message)) => message,
+        (Some(view), None) => source_status(app, view),
+        (None, None) if app.editing_filter || !app.filter.is_empty() => {
+            format!("Filter: {}", app.filter)
+        }
+        (None, None) => String::new(),
+    };
+    let keys_text = if app.editing_filter {
+        KEYS_FILTER
+    } else if app.source.is_some() {
+        KEYS_SOURCE
+    } else {
+        KEYS_LIST
+    };
+
+    match &mut app.source {
+        Some(view) => draw_source(frame, body, view),
+        None => draw_list(frame, body, app),
+    }
+    frame.render_widget(
+        Paragraph::new(status_text).style(Style::new().add_modifier(Modifier::BOLD)),
+        status,
+    );
+    frame.render_widget(
+        Paragraph::new(keys_text).style(Style::new().fg(Color::DarkGray)),
+        keys,
+    );
+}
+
+fn percent(covered: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", covered as f64 * 100.0 / total as f64)
+    }
+}
+
+fn entry_row(entry: &Entry) -> Row<'_> {
+    Row::new([
+        entry.uncovered_lines.to_string(),
+        entry.predicates.to_string(),
+        entry.regions.to_string(),
+        percent(entry.total_lines - entry.uncovered_lines, entry.total_lines),
+        entry.name.clone(),
+    ])
+}
+
+fn draw_list(frame: &mut Frame, area: Rect, app: &App) {
+    let entries = app.visible_entries();
+    let title = format!(
+        " {} ({}{}) ",
+        match app.list {
+            ListKind::Files => "Files",
+            ListKind::Functions => "Functions",
+        },
+        entries.len(),
+        if app.show_covered {
+            ""
+        } else {
+            ", partially covered"
+        }
+    );
+    let table = Table::new(
+        entries.iter().map(|entry| entry_row(entry)),
+        [
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(7),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(["Uncovered", "One-sided", "Regions", "Lines", "Name"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::new().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+    let mut state = TableState::new().with_selected(Some(app.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Describes the one-sided predicates on the cursor line and, if several binaries were merged,
+/// how often each of them took the predicate.
+fn source_status(app: &App, view: &SourceView) -> String {
+    let mut parts = Vec::new();
+    for predicate in view.predicates_at_cursor() {
+        let mut part = format!(
+            "{}:{} {}",
+            predicate.start.0, predicate.start.1, predicate.label
+        );
+        let key = (view.file_path.clone(), predicate.start.0, predicate.start.1);
+        if let Some(counts) = app
+            .attribution
+            .as_ref()
+            .and_then(|attribution| attribution.get(&key))
+        {
+            for (binary, true_count, false_count) in counts {
+                let name = binary.rsplit('/').next().unwrap_or(binary);
+                part.push_str(&format!(" | {} T:{} F:{}", name, true_count, false_count));
+            }
+        }
+        parts.push(part);
+    }
+    if parts.is_empty() {
+        format!("{}:{}", view.file_path, view.cursor)
+    } else {
+        parts.join("; ")
+    }
+}
+
+/// The style of every byte of the line, from the highlights that cover it.
+fn line_styles(view: &SourceView, number: u64, text: &str) -> Vec<Style> {
+    let mut styles = vec![Style::new(); text.len()];
+    let column_offset = |column: u64| (column.saturating_sub(1) as usize).min(text.len());
+    for highlight in &view.highlights {
+        if highlight.start.0 > number || highlight.end.0 < number {
+            continue;
+        }
+        let start = if highlight.start.0 == number {
+            column_offset(highlight.start.1)
+        } else {
+            0
+        };
+        let end = if highlight.end.0 == number {
+            column_offset(highlight.end.1)
+        } else {
+            text.len()
+        };
+        let style = match highlight.kind {
+            HighlightKind::UncoveredRegion => Style::new().fg(Color::Red),
+            HighlightKind::OneSided => Style::new().fg(Color::Black).bg(Color::Yellow),
+        };
+        if start < end {
+            styles[start..end].fill(style);
+        }
+    }
+    styles
+}
+
+fn source_line<'a>(view: &SourceView, number: u64, count_width: usize) -> Line<'a> {
+    let text = &view.lines[number as usize - 1];
+    let count = view.counts.get(&number);
+    let count_style = match count {
+        Some(0) => Style::new().fg(Color::Red),
+        Some(_) => Style::new().fg(Color::Green),
+        None => Style::new(),
+    };
+    let mut spans = vec![
+        Span::styled(format!("{:>5} ", number), Style::new().fg(Color::DarkGray)),
+        Span::styled(
+            format!(
+                "{:>width$} | ",
+                count.map(u64::to_string).unwrap_or_default(),
+                width = count_width
+            ),
+            count_style,
+        ),
+    ];
+
+    // one span for every run of bytes with the same style
+    let styles = line_styles(view, number, text);
+    let mut run_start = 0;
+    for i in 1..=text.len() {
+        if i < text.len() && (styles[i] == styles[run_start] || !text.is_char_boundary(i)) {
+            continue;
+        }
+        spans.push(Span::styled(
+            text[run_start..i].replace('\t', "    "),
+            styles[run_start],
+        ));
+        run_start = i;
+    }
+
+    let line = Line::from(spans);
+    if number == view.cursor {
+        line.style(Style::new().add_modifier(Modifier::REVERSED))
+    } else {
+        line
+    }
+}
+
+fn draw_source(frame: &mut Frame, area: Rect, view: &mut SourceView) {
+    // keep the cursor on the screen
+    let height = area.height.saturating_sub(2).max(1) as u64;
+    if view.cursor < view.scroll {
+        view.scroll = view.cursor;
+    } else if view.cursor >= view.scroll + height {
+        view.scroll = view.cursor + 1 - height;
+    }
+
+    let last = (view.scroll + height - 1).min(view.lines.len() as u64);
+    let count_width = view
+        .counts
+        .values()
+        .map(|count| count.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let lines: Vec<Line> = (view.scroll..=last)
+        .map(|number| source_line(view, number, count_width))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::new()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", view.file_path)),
+        ),
+        area,
+    );
+}
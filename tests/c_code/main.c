@@ -1,4 +1,5 @@
 #include <stdio.h>
+#include <stdlib.h>
 
 void test_function(int x) {
     int squared = x * x;
@@ -39,7 +40,117 @@ void test_function(int x) {
     // Again, this is a line comment.
 }
 
+#define CHECK(cond) do { if (!(cond)) { printf("Check failed: %s\n", #cond); } } while (0)
+
+void test_macro(int x) {
+    CHECK(x > 0);
+}
+
+void test_compound(int a, int b, int c) {
+    if ((a > 0 && b > 0) || c > 0) {
+        printf("Compound condition holds\n");
+    }
+}
+
+void test_loop(int n) {
+    for (int i = 0; i < n; i++) {
+        printf("Iteration %d\n", i);
+    }
+}
+
+void test_switch(int op) {
+    switch (op) {
+    case 0:
+        printf("Zero\n");
+        break;
+    case 1:
+        printf("One\n");
+        break;
+    default:
+        printf("Other\n");
+        break;
+    }
+}
+
+int test_error_path(int n) {
+    char *buf = malloc(n);
+    if (!buf) {
+        perror("malloc");
+        return -1;
+    }
+    if (n > 100) {
+        fprintf(stderr, "Size too large: %d\n", n);
+        free(buf);
+        return -1;
+    }
+    free(buf);
+    return 0;
+}
+
+void test_tokens(int x) {
+    if (x < 0) {
+        printf("/*");
+    }
+    if (x < 0) {
+        // this comment continues \
+        on this line
+    }
+    if (x < 0) { ; }
+}
+
+#define DEF(name) int name(int x) { if (x > 0) { return 1; } return 0; }
+
+DEF(def_a)
+DEF(def_b)
+
+void test_nested(int x) {
+    if (x > 0) {
+        if (x > 1) {
+            if (x > 2) {
+                if (x > 3) {
+                    if (x > 4) {
+                        printf("Deep: %d\n", x);
+                    }
+                }
+            }
+        }
+    }
+}
+
+void test_loop_many(int n) {
+    int i = 0;
+    while (i < n) {
+        printf("Iteration %d\n", i);
+        i++;
+    }
+}
+
+static int scale(int x) {
+    return x * 2;
+}
+
+int test_static_call(int x) {
+    return scale(x) + 1;
+}
+
+// the case test_switch takes, the tests merge a build taking another one
+#ifndef SWITCH_OP
+#define SWITCH_OP 1
+#endif
+
 int main() {
     test_function(1);
+    test_macro(1);
+    test_compound(1, 1, 0);
+    test_compound(0, 1, 0);
+    test_loop(1);
+    test_switch(SWITCH_OP);
+    test_error_path(16);
+    test_tokens(1);
+    def_a(1);
+    def_b(0);
+    test_nested(5);
+    test_loop_many(5);
+    test_static_call(1);
     return 0;
 }
@@ -0,0 +1,26 @@
+#include <cstdio>
+
+template <typename T>
+T clamp_positive(T value) {
+    if (value < 0) {
+        return 0;
+    }
+    return value;
+}
+
+namespace shapes {
+struct Square {
+    double side;
+    double area() const { return side * side; }
+};
+}
+
+int main() {
+    // each instantiation takes the branch one way only
+    printf("%d\n", clamp_positive(-1));
+    printf("%f\n", clamp_positive(2.5));
+
+    shapes::Square square{2.0};
+    printf("%f\n", square.area());
+    return 0;
+}
@@ -25,7 +25,7 @@ fn test_llvm_cov_integration() {
 
     // Run 'getcov' to execute the binary and process coverage
     let output = Command::new("cargo")
-        .args(&["run", "--", "--", "tests/c_code/main"])
+        .args(["run", "--", "--", "tests/c_code/main"])
         .output()
         .expect("Failed to run 'getcov'");
     assert!(output.status.success());
@@ -43,7 +43,7 @@ fn test_text_output_format() {
     run_make();
 
     let output = Command::new("cargo")
-        .args(&["run", "--", "--text", "--", "tests/c_code/main"])
+        .args(["run", "--", "--text", "--", "tests/c_code/main"])
         .output()
         .expect("Failed to run 'getcov'");
     assert!(output.status.success());
@@ -61,7 +61,7 @@ fn test_all_functions_flag() {
     run_make();
 
     let output = Command::new("cargo")
-        .args(&["run", "--", "--all", "--", "tests/c_code/main"])
+        .args(["run", "--", "--all", "--", "tests/c_code/main"])
         .output()
         .expect("Failed to run 'getcov'");
     assert!(output.status.success());
@@ -84,7 +84,7 @@ fn test_input_directory() {
     std::fs::write("tests/inputs/test2.txt", "test input 2").expect("Failed to write test file");
 
     let output = Command::new("cargo")
-        .args(&[
+        .args([
             "run",
             "--",
             "--input",
@@ -109,7 +109,7 @@ fn test_invalid_binary() {
     run_make();
 
     let output = Command::new("cargo")
-        .args(&["run", "--", "--", "nonexistent_binary"])
+        .args(["run", "--", "--", "nonexistent_binary"])
         .output()
         .expect("Failed to run 'getcov'");
 
@@ -119,3 +119,1336 @@ fn test_invalid_binary() {
 
     run_make_clean();
 }
+
+#[test]
+#[serial]
+fn test_line_coverage_output() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--lines", "--text", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("Line Coverage Report"));
+    assert!(stdout.contains("main.c"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_macro_expansion_report() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--text", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // The predicate of CHECK(x > 0) is reported at the macro definition and its use site
+    assert!(stdout.contains("inside macro CHECK used at"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_mcdc_output() {
+    let status = Command::new("make")
+        .arg("main_mcdc")
+        .current_dir("tests/c_code")
+        .status()
+        .expect("Failed to compile C program with MC/DC");
+    assert!(status.success());
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--mcdc",
+            "--text",
+            "--",
+            "tests/c_code/main_mcdc",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // test_compound never shows that `b > 0` and `c > 0` affect the decision on their own
+    assert!(stdout.contains("MC/DC Coverage Report"));
+    assert!(stdout.contains("C2: b > 0"));
+    assert!(stdout.contains("C3: c > 0"));
+    assert!(stdout.contains("missing pair"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_bias_threshold() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--text",
+            "--bias-threshold",
+            "1",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // The biased branches are reported next to the uncovered report
+    assert!(stdout.contains("Partially Covered Functions Report"));
+    assert!(stdout.contains("Biased Branches"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_loop_report() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--loops", "--text", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // test_loop(1) runs the loop body exactly once
+    assert!(stdout.contains("Never iterated more than once"));
+    assert!(stdout.contains("test_loop ("));
+    // test_loop_many(5) iterates five times and leaves the loop through its condition
+    assert!(!stdout.contains("test_loop_many"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_switch_report() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--switches",
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // test_switch(1) never takes `case 0` and `default`
+    assert!(stdout.contains("Never taken: case 0, default"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_dict_output() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "dict", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // `cubed < 100` is always true for test_function(1)
+    assert!(stdout.contains("=\"100\""));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_region_categories() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--text",
+            "--exclude-category",
+            "allocation-failure",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // the `n > 100` branch of test_error_path bails out with `return -1`
+    assert!(stdout.contains("error-handling)"));
+    // the `!buf` branch after `malloc` is excluded
+    assert!(!stdout.contains("allocation-failure)"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_regions_without_executable_code() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--function",
+            "test_tokens",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let reports: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to parse JSON");
+    let start_lines: Vec<u64> = reports[0]["uncovered_regions"]
+        .as_array()
+        .expect("Missing uncovered regions")
+        .iter()
+        .map(|guarded| guarded["region"]["start_line"].as_u64().unwrap())
+        .collect();
+    // the `"/*"` string doesn't start a comment, so the printf is kept
+    assert!(start_lines.contains(&91));
+    // a comment continued with `\` and a block of only `;` have no executable code
+    assert!(!start_lines.contains(&94));
+    assert!(!start_lines.contains(&98));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_function_report() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--function",
+            "test_error_.*",
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("Function: test_error_path"));
+    assert!(stdout.contains("guarded by: !buf (true 0, false 1)"));
+    assert!(stdout.contains("Callers:"));
+    assert!(stdout.contains("  main (executed 1 times)"));
+
+    // llvm-cov names static functions after their file, e.g. `main.c:scale`
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--function",
+            "test_static_call|.*:scale",
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("Callees:\n  main.c:scale (executed 1 times)"));
+    assert!(stdout.contains("Callers:\n  test_static_call (executed 1 times)"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_function_filters() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--exclude-function",
+            "^test_error_path$",
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("Function: test_function"));
+    assert!(!stdout.contains("Function: test_error_path"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_file_filters() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--exclude-file",
+            r"main\.c$",
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("No partially covered functions found."));
+    assert!(!stdout.contains("Function: "));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--include-file",
+            r"c_code/main\.c$",
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("Function: test_function"));
+    assert!(stdout.contains("Function: test_error_path"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_path_map() {
+    run_make();
+
+    // pretend the sources moved to another directory after the build
+    let moved_dir = std::env::temp_dir().join("getcov_path_map");
+    std::fs::create_dir_all(&moved_dir).expect("Failed to create directory");
+    std::fs::copy("tests/c_code/main.c", moved_dir.join("main.c")).expect("Failed to copy source");
+    let build_dir = std::fs::canonicalize("tests/c_code").expect("Failed to resolve path");
+    let path_map = format!("{}={}", build_dir.display(), moved_dir.display());
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--path-map",
+            &path_map,
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains(&format!("Location: {}", moved_dir.join("main.c").display())));
+
+    // a rule whose target is below its source is applied only once
+    let nested_dir = build_dir.join("src");
+    std::fs::create_dir_all(&nested_dir).expect("Failed to create directory");
+    std::fs::copy("tests/c_code/main.c", nested_dir.join("main.c")).expect("Failed to copy source");
+    let path_map = format!("{}={}", build_dir.display(), nested_dir.display());
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--path-map",
+            &path_map,
+            "--text",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains(&format!(
+        "Location: {}",
+        nested_dir.join("main.c").display()
+    )));
+    assert!(!stdout.contains("src/src"));
+
+    std::fs::remove_dir_all(&nested_dir).expect("Failed to remove directory");
+    std::fs::remove_dir_all(&moved_dir).expect("Failed to remove directory");
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_unreadable_sources() {
+    run_make();
+
+    // map the sources to a directory that doesn't exist, so none of them can be read
+    let build_dir = std::fs::canonicalize("tests/c_code").expect("Failed to resolve path");
+    let path_map = format!("{}=/nonexistent/getcov_sources", build_dir.display());
+
+    for format in ["text", "json", "sarif", "markdown"] {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--path-map",
+                &path_map,
+                "--format",
+                format,
+                "--",
+                "tests/c_code/main",
+            ])
+            .output()
+            .expect("Failed to run 'getcov'");
+        assert!(output.status.success(), "--format {} failed", format);
+
+        // the snippets of the text formats say that the source is missing
+        let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+        if matches!(format, "text" | "markdown") {
+            assert!(stdout.contains("<source unavailable: /nonexistent/getcov_sources/main.c>"));
+        }
+    }
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_diff_saved_outputs() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+    let saved_output = std::env::temp_dir().join("getcov_diff.json");
+    std::fs::write(&saved_output, &output.stdout).expect("Failed to write output");
+    let saved_output = saved_output
+        .to_str()
+        .expect("Path contains invalid Unicode");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            "--base",
+            saved_output,
+            "--head",
+            saved_output,
+            "--text",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // comparing a run with itself changes nothing
+    assert!(stdout.contains("Coverage Diff"));
+    assert!(stdout.contains("(0 covered)"));
+    assert!(!stdout.contains("Newly"));
+
+    // the diff goes to the files given like the outputs of the analysis
+    let text_output = std::env::temp_dir().join("getcov_diff.txt");
+    let json_output = std::env::temp_dir().join("getcov_diff_emit.json");
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            "--base",
+            saved_output,
+            "--head",
+            saved_output,
+            "--text",
+            "-o",
+        ])
+        .arg(&text_output)
+        .arg("--emit")
+        .arg(format!("json={}", json_output.display()))
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let text = std::fs::read_to_string(&text_output).expect("Failed to read text output");
+    assert!(text.contains("Coverage Diff"));
+    let json = std::fs::read_to_string(&json_output).expect("Failed to read JSON output");
+    let diff: serde_json::Value = serde_json::from_str(&json).expect("Failed to parse diff");
+    assert_eq!(diff["delta"]["covered_branches"], 0);
+
+    std::fs::remove_file(text_output).expect("Failed to remove file");
+    std::fs::remove_file(json_output).expect("Failed to remove file");
+    std::fs::remove_file(saved_output).expect("Failed to remove file");
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_diff_function_missing_from_base() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+
+    // in the base, test_error_path was never called, so the saved output doesn't list it
+    let mut json: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse JSON");
+    json["uncovered_functions"]
+        .as_array_mut()
+        .expect("No uncovered functions")
+        .retain(|function| function["function_name"] != "test_error_path");
+    json["uncalled_functions"]
+        .as_array_mut()
+        .expect("No uncalled functions")
+        .push("test_error_path".into());
+    // an output of an older version doesn't list the uncalled functions either
+    let mut unversioned = json.clone();
+    unversioned
+        .as_object_mut()
+        .expect("The output is not an object")
+        .remove("uncalled_functions");
+
+    let dir = std::env::temp_dir().join("getcov_diff_missing_function");
+    std::fs::create_dir_all(&dir).expect("Failed to create directory");
+    let head = dir.join("head.json");
+    std::fs::write(&head, &stdout).expect("Failed to write head output");
+
+    for (base, newly_covered_functions) in [(json, vec!["test_error_path"]), (unversioned, vec![])]
+    {
+        let base_path = dir.join("base.json");
+        std::fs::write(&base_path, base.to_string()).expect("Failed to write base output");
+
+        let output = Command::new("cargo")
+            .args(["run", "--", "diff", "--base"])
+            .arg(&base_path)
+            .arg("--head")
+            .arg(&head)
+            .output()
+            .expect("Failed to run 'getcov diff'");
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+        let diff: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse diff");
+
+        assert_eq!(
+            diff["newly_covered_functions"],
+            serde_json::json!(newly_covered_functions)
+        );
+        // the parts the head leaves uncovered were uncovered or unknown in the base
+        assert_eq!(diff["newly_uncovered_regions"], serde_json::json!([]));
+        assert_eq!(
+            diff["newly_uncovered_branch_directions"],
+            serde_json::json!([])
+        );
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_diff_profdata_against_saved_output() {
+    run_make();
+    let status = Command::new("make")
+        .arg("templates")
+        .current_dir("tests/c_code")
+        .status()
+        .expect("Failed to compile C++ program");
+    assert!(status.success());
+
+    let dir = std::env::temp_dir().join("getcov_diff_profdata");
+    std::fs::create_dir_all(&dir).expect("Failed to create directory");
+
+    // the C++ program checks that both sides see demangled and merged function names
+    for binary in ["tests/c_code/main", "tests/c_code/templates"] {
+        let output = Command::new("cargo")
+            .args(["run", "--", "--", binary])
+            .output()
+            .expect("Failed to run 'getcov'");
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+        let json: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse JSON");
+        let profdata = json["metadata"]["binaries"][0]["profdata"]
+            .as_str()
+            .expect("No profdata file in the metadata")
+            .to_string();
+        let saved_output = dir.join("output.json");
+        std::fs::write(&saved_output, &stdout).expect("Failed to write output");
+
+        // the profdata file and the saved output of the same run compare equal either way
+        for (base, head) in [
+            (profdata.as_str(), saved_output.to_str().unwrap()),
+            (saved_output.to_str().unwrap(), profdata.as_str()),
+        ] {
+            let output = Command::new("cargo")
+                .args([
+                    "run", "--", "diff", "--base", base, "--head", head, "--", binary,
+                ])
+                .output()
+                .expect("Failed to run 'getcov diff'");
+            assert!(output.status.success());
+            let stdout =
+                String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+            let diff: serde_json::Value =
+                serde_json::from_str(&stdout).expect("Failed to parse diff");
+
+            for (field, value) in diff["delta"].as_object().expect("No delta") {
+                assert_eq!(value, 0, "{} of {} changed", field, binary);
+            }
+            for field in [
+                "newly_covered_functions",
+                "newly_uncovered_functions",
+                "newly_covered_regions",
+                "newly_uncovered_regions",
+                "newly_covered_branch_directions",
+                "newly_uncovered_branch_directions",
+                "predicates_now_both_sided",
+            ] {
+                assert_eq!(
+                    diff[field],
+                    serde_json::json!([]),
+                    "{} of {}",
+                    field,
+                    binary
+                );
+            }
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_merge_binaries() {
+    run_make();
+
+    std::fs::create_dir_all("tests/merge_inputs").expect("Failed to create test directory");
+    std::fs::write("tests/merge_inputs/input", "test input").expect("Failed to write test file");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--text",
+            "--merge",
+            "tests/c_code/main=tests/merge_inputs",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("Partially Covered Functions Report"));
+    assert!(stdout.contains("Coverage by Binary"));
+
+    std::fs::remove_dir_all("tests/merge_inputs").expect("Failed to clean up test directory");
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_merge_different_binaries() {
+    run_make();
+    let status = Command::new("make")
+        .arg("main_case0")
+        .current_dir("tests/c_code")
+        .status()
+        .expect("Failed to compile C program");
+    assert!(status.success());
+
+    std::fs::create_dir_all("tests/merge_case0_inputs").expect("Failed to create test directory");
+    std::fs::write("tests/merge_case0_inputs/input", "test input")
+        .expect("Failed to write test file");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--merge",
+            "tests/c_code/main_case0=tests/merge_case0_inputs",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse JSON");
+    let binaries = json["binaries"].as_array().expect("No binaries");
+    assert_eq!(binaries.len(), 2);
+    assert!(binaries[1]["binary"]
+        .as_str()
+        .is_some_and(|binary| binary.ends_with("tests/c_code/main_case0")));
+
+    // only the second binary takes `case 0` on line 63
+    let takes_case0 = |binary: &serde_json::Value| {
+        binary["unique_branch_directions"]
+            .as_array()
+            .expect("No branch directions")
+            .iter()
+            .any(|direction| direction["branch"]["line"] == 63 && direction["direction"] == true)
+    };
+    assert!(!takes_case0(&binaries[0]));
+    assert!(takes_case0(&binaries[1]));
+
+    // the merged coverage is the union of both binaries
+    let covered = |coverage: &serde_json::Value| coverage["covered_branches"].as_u64().unwrap();
+    assert!(covered(&json["coverage"]) > covered(&binaries[0]["coverage"]));
+    assert!(covered(&json["coverage"]) > covered(&binaries[1]["coverage"]));
+
+    std::fs::remove_dir_all("tests/merge_case0_inputs").expect("Failed to clean up test directory");
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_demangling() {
+    let status = Command::new("make")
+        .arg("templates")
+        .current_dir("tests/c_code")
+        .status()
+        .expect("Failed to compile C++ program");
+    assert!(status.success());
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--all",
+            "--text",
+            "--",
+            "tests/c_code/templates",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // both instantiations of the template are listed under the source function
+    assert!(stdout.contains(". clamp_positive ("));
+    assert!(stdout.contains("clamp_positive<int>(int) (executed 1 times)"));
+    assert!(stdout.contains("clamp_positive<double>(double) (executed 1 times)"));
+    assert!(stdout.contains("shapes::Square::area() const"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_macro_defined_functions_not_merged() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--all", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse JSON");
+    let functions = json["functions"].as_array().expect("No functions");
+    // def_a and def_b share the body of the DEF macro, but aren't instantiations of one function
+    for name in ["def_a", "def_b"] {
+        let function = functions
+            .iter()
+            .find(|function| function["name"] == name)
+            .unwrap_or_else(|| panic!("{} is missing", name));
+        assert_eq!(function["instantiations"], serde_json::json!([]));
+    }
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_lcov_export() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--format",
+            "lcov",
+            "--exclude-function",
+            "^test_error_path$",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("SF:"));
+    assert!(stdout.contains(",test_function\n"));
+    assert!(stdout.contains("FNDA:1,main\n"));
+    // `if (x > 10)` in test_function is only false, the loop condition goes both ways once
+    assert!(stdout.contains("BRDA:12,0,0,0\nBRDA:12,0,1,1\n"));
+    assert!(stdout.contains("BRDA:56,0,0,1\nBRDA:56,0,1,1\n"));
+    assert!(stdout.contains("\nDA:5,1\n"));
+    assert!(stdout.contains("end_of_record"));
+    assert!(!stdout.contains("test_error_path"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_cobertura_export() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--format",
+            "cobertura",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.starts_with("<?xml"));
+    assert!(stdout.contains("<coverage line-rate="));
+    assert!(stdout.contains(r#"filename="main.c""#));
+    assert!(stdout.contains(r#"branch="true" condition-coverage=""#));
+    assert!(stdout.trim_end().ends_with("</coverage>"));
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_html_report() {
+    run_make();
+
+    let dir = std::env::temp_dir().join("getcov_html_report");
+    let _ = std::fs::remove_dir_all(&dir);
+    let output = Command::new("cargo")
+        .args(["run", "--", "--html"])
+        .arg(&dir)
+        .args(["--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let index = std::fs::read_to_string(dir.join("index.html")).expect("No index.html");
+    assert!(index.contains("main.c"));
+    assert!(index.contains("<tr class=\"partial\"><td>test_function</td>"));
+    assert!(index.contains("source-0.html#L"));
+    assert!(!index.contains("http"));
+
+    let source = std::fs::read_to_string(dir.join("source-0.html")).expect("No source page");
+    assert!(source.contains("class=\"one-sided\""));
+    assert!(source.contains("uncovered-region"));
+    assert!(source.contains("<span class=\"keyword\">if</span>"));
+    assert!(dir.join("style.css").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_markdown_summary() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--format",
+            "markdown",
+            "--top",
+            "1",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("## Coverage Summary"));
+    assert!(stdout.contains("| Branches |"));
+    assert!(stdout.contains("### Partially Covered Functions (1 of"));
+    assert!(stdout.contains("#### `test_function`"));
+    assert!(stdout.contains("<details><summary>Source</summary>"));
+
+    // the same change as a list entry and as a unified diff, e.g. of `git diff`
+    let diff = [
+        "diff --git a/tests/c_code/main.c b/tests/c_code/main.c",
+        "--- a/tests/c_code/main.c",
+        "+++ b/tests/c_code/main.c",
+        "@@ -62,2 +62,3 @@ void test_switch(int op) {",
+        "     switch (op) {",
+        "+    case 0:",
+        "         printf(\"Zero\\n\");",
+        "",
+    ]
+    .join("\n");
+    let changes = std::env::temp_dir().join("getcov_changed_lines.txt");
+    for content in ["tests/c_code/main.c:63\n", &diff] {
+        std::fs::write(&changes, content).expect("Failed to write changes");
+        let output = Command::new("cargo")
+            .args(["run", "--", "--format", "markdown", "--changed"])
+            .arg(&changes)
+            .args(["--", "tests/c_code/main"])
+            .output()
+            .expect("Failed to run 'getcov'");
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+        assert!(stdout.contains("### Uncovered Changed Lines"));
+        assert!(stdout.contains("#### `test_switch`"));
+        assert!(stdout.contains("`case 0`"));
+        assert!(!stdout.contains("#### `test_function`"));
+    }
+
+    let _ = std::fs::remove_file(&changes);
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_prompt_output() {
+    run_make();
+
+    let run = || {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--format",
+                "prompt",
+                "--budget",
+                "4000",
+                "--",
+                "tests/c_code/main",
+            ])
+            .output()
+            .expect("Failed to run 'getcov'");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).expect("Failed to convert stdout to string")
+    };
+    let stdout = run();
+    assert!(stdout.contains("<<<UNIT "));
+    assert!(stdout.contains("<<<UNIT_END>>>"));
+    assert!(stdout.contains("Goal: an input that makes the predicate"));
+    assert!(stdout.contains("### Enclosing Function"));
+    assert!(stdout.contains("Expanded from macro `CHECK`"));
+    assert!(stdout.contains("#define CHECK(cond)"));
+    assert!(stdout.contains("- `int main()`"));
+
+    // the unit ids only depend on the code, not on the run
+    let ids = |stdout: &str| -> Vec<String> {
+        stdout
+            .lines()
+            .filter(|line| line.starts_with("<<<UNIT "))
+            .map(str::to_string)
+            .collect()
+    };
+    assert_eq!(ids(&stdout), ids(&run()));
+
+    // the markers of elided lines in deeply indented code have to fit into small budgets
+    let mut elided = false;
+    for budget in (100..=400).step_by(20) {
+        let output = Command::new("cargo")
+            .args(["run", "--", "--format", "prompt", "--budget"])
+            .arg(budget.to_string())
+            .args([
+                "--include-function",
+                "^test_nested$",
+                "--",
+                "tests/c_code/main",
+            ])
+            .output()
+            .expect("Failed to run 'getcov'");
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+        elided |= stdout.contains("lines elided");
+    }
+    assert!(elided);
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_show_source() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "show", "main.c", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    // not a terminal, so without color
+    assert!(!stdout.contains('\x1b'));
+    assert!(stdout
+        .lines()
+        .next()
+        .unwrap()
+        .ends_with("tests/c_code/main.c"));
+    assert!(stdout.contains("|#include <stdio.h>"));
+    assert!(stdout.contains("^ True: 0, False: 1"));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "show",
+            "test_switch",
+            "--color",
+            "always",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    assert!(stdout.contains("test_switch ("));
+    assert!(stdout.contains("\x1b["));
+    assert!(stdout.contains("switch (op) {"));
+    assert!(!stdout.contains("void test_macro"));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "show",
+            "no_such_function",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(!output.status.success());
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_tui_needs_terminal() {
+    // the output of the test is not a terminal, so the browser refuses to start
+    let output = Command::new("cargo")
+        .args(["run", "--", "tui", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).expect("Failed to convert stderr to string");
+    assert!(stderr.contains("needs a terminal"));
+}
+
+#[test]
+#[serial]
+fn test_emit_outputs() {
+    run_make();
+
+    let dir = std::env::temp_dir().join("getcov_emit_outputs");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Failed to create output directory");
+    let output = Command::new("cargo")
+        .args(["run", "--", "--format", "text", "-o"])
+        .arg(dir.join("report.txt"))
+        .arg("--emit")
+        .arg(format!("json={}", dir.join("report.json").display()))
+        .arg("--emit")
+        .arg(format!("lcov={}", dir.join("lcov.info").display()))
+        .arg("--manifest")
+        .arg(dir.join("manifest.json"))
+        .args(["--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let text = std::fs::read_to_string(dir.join("report.txt")).expect("No text output");
+    assert!(text.contains("Function: test_function"));
+    let json = std::fs::read_to_string(dir.join("report.json")).expect("No JSON output");
+    let json: serde_json::Value = serde_json::from_str(&json).expect("Failed to parse JSON");
+    assert!(json["uncovered_functions"].is_array());
+    let lcov = std::fs::read_to_string(dir.join("lcov.info")).expect("No LCOV output");
+    assert!(lcov.contains("end_of_record"));
+
+    let manifest = std::fs::read_to_string(dir.join("manifest.json")).expect("No manifest");
+    let manifest: serde_json::Value =
+        serde_json::from_str(&manifest).expect("Failed to parse manifest");
+    // the binary is recorded with its canonical path
+    assert!(manifest["binaries"][0]["binary"]
+        .as_str()
+        .is_some_and(|binary| binary.ends_with("tests/c_code/main")));
+    let formats: Vec<&str> = manifest["outputs"]
+        .as_array()
+        .expect("No outputs in manifest")
+        .iter()
+        .filter_map(|output| output["format"].as_str())
+        .collect();
+    assert_eq!(formats, ["text", "json", "lcov"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_sarif_output() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--format", "sarif", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    let log: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse SARIF");
+    assert_eq!(log["version"], "2.1.0");
+    let results = log["runs"][0]["results"]
+        .as_array()
+        .expect("No results in the SARIF log");
+    assert!(results
+        .iter()
+        .any(|result| result["ruleId"] == "always-false"));
+    assert!(results
+        .iter()
+        .any(|result| result["ruleId"] == "uncovered-region"));
+    for result in results {
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "tests/c_code/main.c");
+        assert!(location["region"]["snippet"]["text"].is_string());
+        assert!(result["partialFingerprints"]["getcovFinding/v1"].is_string());
+    }
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--all",
+            "--format",
+            "sarif",
+            "--",
+            "tests/c_code/main",
+        ])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(!output.status.success());
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_json_schema_files_up_to_date() {
+    for (args, file) in [
+        (vec!["schema"], "schema/uncovered.schema.json"),
+        (vec!["schema", "--all"], "schema/functions.schema.json"),
+    ] {
+        let output = Command::new("cargo")
+            .args(["run", "--"])
+            .args(&args)
+            .output()
+            .expect("Failed to run 'getcov'");
+        assert!(output.status.success());
+
+        // a changed schema has to be committed, and may need a new schema version
+        let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+        let committed = std::fs::read_to_string(file).expect("No committed schema");
+        assert_eq!(stdout.trim(), committed.trim(), "{} is out of date", file);
+    }
+}
+
+#[test]
+#[serial]
+fn test_json_output_matches_schema() {
+    run_make();
+
+    for (args, file) in [
+        (
+            vec!["--bias-threshold", "2"],
+            "schema/uncovered.schema.json",
+        ),
+        (vec!["--all"], "schema/functions.schema.json"),
+    ] {
+        let output = Command::new("cargo")
+            .args(["run", "--"])
+            .args(&args)
+            .args(["--", "tests/c_code/main"])
+            .output()
+            .expect("Failed to run 'getcov'");
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+        let json: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse JSON");
+        let schema: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(file).expect("No committed schema"))
+                .expect("Failed to parse schema");
+        if let Err(error) = jsonschema::validate(&schema, &json) {
+            panic!(
+                "The output does not match {} at {}: {}",
+                file,
+                error.instance_path(),
+                error
+            );
+        }
+
+        assert_eq!(json["schema_version"], 1);
+        let binary = &json["metadata"]["binaries"][0];
+        assert!(binary["binary"]
+            .as_str()
+            .is_some_and(|binary| binary.ends_with("tests/c_code/main")));
+        assert_eq!(binary["sha256"].as_str().map(str::len), Some(64));
+        assert_eq!(binary["seed_count"], 1);
+    }
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_all_output_wraps_unversioned_array() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--all", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    // before the format was versioned, `--all` printed the array that is now in `functions`
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse JSON");
+    let functions = &json["functions"];
+    let schema: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string("schema/functions.schema.json").expect("No committed schema"),
+    )
+    .expect("Failed to parse schema");
+    let array_schema = serde_json::json!({
+        "type": "array",
+        "items": { "$ref": "#/$defs/FunctionInfo" },
+        "$defs": schema["$defs"],
+    });
+    if let Err(error) = jsonschema::validate(&array_schema, functions) {
+        panic!(
+            "The functions don't match the unversioned output at {}: {}",
+            error.instance_path(),
+            error
+        );
+    }
+
+    // the fields of the unversioned output are all still there
+    for function in functions.as_array().expect("No functions") {
+        assert!(function["name"].is_string());
+        assert!(function["file_path"].is_string());
+        for field in ["start_line", "start_column", "end_line", "end_column"] {
+            assert!(function[field].is_u64(), "{} is missing", field);
+        }
+    }
+
+    run_make_clean();
+}
+
+#[test]
+#[serial]
+fn test_diff_reads_unversioned_output() {
+    run_make();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--", "tests/c_code/main"])
+        .output()
+        .expect("Failed to run 'getcov'");
+    assert!(output.status.success());
+
+    // outputs written before the format was versioned lack the version and the metadata
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    let mut json: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse JSON");
+    let object = json.as_object_mut().expect("The output is not an object");
+    object.remove("schema_version");
+    object.remove("metadata");
+
+    let dir = std::env::temp_dir().join("getcov_unversioned_output");
+    std::fs::create_dir_all(&dir).expect("Failed to create directory");
+    let base = dir.join("base.json");
+    let head = dir.join("head.json");
+    std::fs::write(&base, json.to_string()).expect("Failed to write base output");
+    std::fs::write(&head, &stdout).expect("Failed to write head output");
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "diff", "--base"])
+        .arg(&base)
+        .arg("--head")
+        .arg(&head)
+        .output()
+        .expect("Failed to run 'getcov diff'");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("Failed to convert stdout to string");
+    let diff: serde_json::Value = serde_json::from_str(&stdout).expect("Failed to parse diff");
+    assert_eq!(diff["delta"]["covered_branches"], 0);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    run_make_clean();
+}